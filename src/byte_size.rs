@@ -41,5 +41,26 @@ pub mod globals {
         /// KV record identifier
         pub const IDENT: [u8; 3] = [0x1, 0x1E, 120]; // \x01x
         pub const IDENT_HEAD_BS: usize = IDENT.len() + K_LEN_BS + TN_LEN_BS + V_LEN_BS;
+
+        /// KV expiry identifier, followed by key length, expiry time and key
+        pub const EXPIRE_IDENT: [u8; 3] = [0x4, 0x1E, 120]; // \x04x
+        pub const EXPIRE_HEAD_BS: usize = EXPIRE_IDENT.len() + K_LEN_BS + U64_BS;
+
+        /// KV key rule identifier, followed by rule length and the serialized rule
+        pub const RULE_IDENT: [u8; 3] = [0x5, 0x1E, 120]; // \x05x
+        pub const RULE_HEAD_BS: usize = RULE_IDENT.len() + V_LEN_BS;
+
+        /// KV key schema identifier, followed by schema length and the serialized schema
+        pub const SCHEMA_IDENT: [u8; 3] = [0x6, 0x1E, 120]; // \x06x
+        pub const SCHEMA_HEAD_BS: usize = SCHEMA_IDENT.len() + V_LEN_BS;
+
+        /// KV index identifier, followed by index length and the serialized definition and entries
+        pub const INDEX_IDENT: [u8; 3] = [0x7, 0x1E, 120]; // \x07x
+        pub const INDEX_HEAD_BS: usize = INDEX_IDENT.len() + V_LEN_BS;
+
+        /// KV tree identifier, followed by name length, section length, name and the tree's
+        /// records and metadata
+        pub const TREE_IDENT: [u8; 3] = [0x8, 0x1E, 120]; // \x08x
+        pub const TREE_HEAD_BS: usize = TREE_IDENT.len() + K_LEN_BS + U64_BS;
     }
 }
@@ -0,0 +1,76 @@
+//! Bounded undo/redo journal shared by the database implementations
+//!
+
+use std::collections::VecDeque;
+
+pub struct Journal<E> {
+    depth: usize,
+    undo: VecDeque<E>,
+    redo: Vec<E>,
+}
+
+impl<E> Journal<E> {
+    /// Create a journal keeping at most `depth` undoable operations
+    ///
+    pub fn new(depth: usize) -> Self {
+        Journal {
+            depth,
+            undo: VecDeque::with_capacity(depth),
+            redo: Vec::new(),
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Record a new operation, this clears the redo history
+    ///
+    pub fn record(&mut self, entry: E) {
+        if self.depth == 0 {
+            return;
+        }
+
+        if self.undo.len() == self.depth {
+            self.undo.pop_front();
+        }
+
+        self.undo.push_back(entry);
+        self.redo.clear();
+    }
+
+    /// Take the latest operation, it's moved to the redo history by `undone`
+    ///
+    pub fn undo(&mut self) -> Option<E> {
+        self.undo.pop_back()
+    }
+
+    /// Take the latest undone operation, it's moved back to the undo history by `redone`
+    ///
+    pub fn redo(&mut self) -> Option<E> {
+        self.redo.pop()
+    }
+
+    pub fn undone(&mut self, entry: E) {
+        self.redo.push(entry);
+    }
+
+    pub fn redone(&mut self, entry: E) {
+        if self.undo.len() == self.depth {
+            self.undo.pop_front();
+        }
+
+        self.undo.push_back(entry);
+    }
+
+    /// Undoable operations, oldest first
+    ///
+    pub fn history(&self) -> impl Iterator<Item = &E> {
+        self.undo.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
@@ -0,0 +1,154 @@
+//! Bitmap operations on KvDb
+//!
+//! Bitmaps are stored as ordinary records of type "bitmap", holding the bits as raw bytes.
+//! Bit offset 0 is the most significant bit of the first byte.
+
+use super::types::{BitOp, Op};
+use super::KvDb;
+use crate::storage::KvInterface;
+use crate::types::bv::{bvbitmap, elements, BvObject, BvString};
+
+/// Bit offsets are limited to 2^32 bits, 512MiB bitmaps
+const MAX_OFFSET: u64 = 1 << 32;
+
+impl<KV> KvDb<KV>
+where
+    KV: KvInterface<Key = BvString, Value = BvObject, RefKey = [u8]>,
+    for<'a> &'a KV: IntoIterator<Item = (&'a BvString, &'a BvObject)>,
+{
+    /// Set the bit at offset and return its previous value
+    ///
+    /// The bitmap is created if the key don't exists and zero padded to fit offset.
+    /// Err if offset is 2^32 or larger.
+    pub fn setbit<S: AsRef<str>>(
+        &mut self,
+        key: S,
+        offset: usize,
+        bit: bool,
+    ) -> Result<bool, String> {
+        if offset as u64 >= MAX_OFFSET {
+            return Err(format!(
+                "Bit offset {} exceeds the maximum of {}",
+                offset,
+                MAX_OFFSET - 1
+            ));
+        }
+
+        let key = key.as_ref().as_bytes();
+
+        match self.get_typed(key, bvbitmap::BITMAP_TYPE)? {
+            Some(bitmap) if bitmap.get_bit(offset) == bit => Ok(bit),
+            Some(_) => Ok(self
+                .modify_checked(Op::SetBit, key, |bitmap| bitmap.set_bit(offset, bit))?
+                .unwrap()),
+            None => {
+                let mut bitmap = bvbitmap::new_object(Vec::new());
+                bitmap.set_bit(offset, bit);
+                self.apply_checked(Op::SetBit, key, Some(bitmap))?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Bit at offset, bits past the end or of a missing key are 0
+    ///
+    pub fn getbit<S: AsRef<str>>(&self, key: S, offset: usize) -> Result<bool, String> {
+        Ok(self
+            .get_typed(key.as_ref().as_bytes(), bvbitmap::BITMAP_TYPE)?
+            .map(|bitmap| bitmap.get_bit(offset))
+            .unwrap_or(false))
+    }
+
+    /// Number of set bits from byte start to byte end(inclusive), negative indexes count from the end
+    ///
+    /// Use `bitcount(key, 0, -1)` to count all bits.
+    pub fn bitcount<S: AsRef<str>>(
+        &self,
+        key: S,
+        start: isize,
+        end: isize,
+    ) -> Result<usize, String> {
+        Ok(
+            match self.get_typed(key.as_ref().as_bytes(), bvbitmap::BITMAP_TYPE)? {
+                Some(bitmap) => match elements::normalize_range(start, end, bitmap.len()) {
+                    Some(range) => bitmap.count_ones(range),
+                    None => 0,
+                },
+                None => 0,
+            },
+        )
+    }
+
+    /// Offset of the first bit equal to `bit` from byte start to byte end(inclusive),
+    /// negative indexes count from the end
+    ///
+    /// Returns None if no such bit exists within the range, bits past the end are not searched.
+    pub fn bitpos<S: AsRef<str>>(
+        &self,
+        key: S,
+        bit: bool,
+        start: isize,
+        end: isize,
+    ) -> Result<Option<usize>, String> {
+        Ok(
+            match self.get_typed(key.as_ref().as_bytes(), bvbitmap::BITMAP_TYPE)? {
+                Some(bitmap) => elements::normalize_range(start, end, bitmap.len())
+                    .and_then(|range| bitmap.bit_position(bit, range)),
+                None => None,
+            },
+        )
+    }
+
+    /// Store the result of a bitwise operation between bitmaps in destination,
+    /// returns the length of destination in bytes
+    ///
+    /// Shorter bitmaps and missing keys are treated as zero padded to the longest bitmap.
+    /// `BitOp::Not` takes exactly one key. An empty result deletes destination.
+    pub fn bitop<S: AsRef<str>>(
+        &mut self,
+        op: BitOp,
+        destination: S,
+        keys: &[S],
+    ) -> Result<usize, String> {
+        if op == BitOp::Not && keys.len() != 1 {
+            return Err("BitOp::Not takes exactly one key".to_string());
+        }
+
+        let bitmaps = keys
+            .iter()
+            .map(|key| {
+                Ok(self
+                    .get_typed(key.as_ref().as_bytes(), bvbitmap::BITMAP_TYPE)?
+                    .map(|bitmap| bitmap.to_vec())
+                    .unwrap_or_default())
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let len = bitmaps.iter().map(|b| b.len()).max().unwrap_or(0);
+        let byte = |bitmap: &Vec<u8>, i: usize| bitmap.get(i).copied().unwrap_or(0);
+
+        let result = (0..len)
+            .map(|i| {
+                let mut bytes = bitmaps.iter().map(|bitmap| byte(bitmap, i));
+                let first = bytes.next().unwrap_or(0);
+
+                match op {
+                    BitOp::And => bytes.fold(first, |acc, b| acc & b),
+                    BitOp::Or => bytes.fold(first, |acc, b| acc | b),
+                    BitOp::Xor => bytes.fold(first, |acc, b| acc ^ b),
+                    BitOp::Not => !first,
+                }
+            })
+            .collect::<Vec<u8>>();
+
+        let destination = destination.as_ref().as_bytes();
+        if result.is_empty() {
+            self.apply(Op::BitOp, destination, None);
+        } else {
+            self.apply_checked(Op::BitOp, destination, Some(bvbitmap::new_object(result)))?;
+            self.expiry.remove(destination);
+        }
+
+        Ok(len)
+    }
+}
@@ -0,0 +1,278 @@
+//! Cache mode of KvDb, evicting keys when a byte or key budget is exceeded
+//!
+//! The size of an entry is the byte length of its key, type name and value. Reads through
+//! `get` and the typed getters count as use. Keys are evicted after each write until the
+//! database is within budget, the written key itself is never evicted by its own write.
+//! Evictions are not journaled and are reported to watchers as `EventKind::Evict`.
+
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+
+use super::types::{CacheConfig, CacheStats, EventKind, EvictionPolicy};
+use super::KvDb;
+use crate::storage::KvInterface;
+use crate::types::bv::{BvObject, BvString};
+
+pub struct Cache {
+    config: CacheConfig,
+    bytes: usize,
+    evictions: u64,
+    /// Behind a lock so reads can stay `&self`
+    tracker: Mutex<Tracker>,
+}
+
+#[derive(Default)]
+struct Tracker {
+    clock: u64,
+    usage: BTreeMap<BvString, Usage>,
+    /// Keys in eviction order, only kept for the LRU and LFU policies
+    order: BTreeSet<(u64, u64, BvString)>,
+}
+
+struct Usage {
+    size: usize,
+    last_used: u64,
+    uses: u64,
+}
+
+impl Tracker {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    /// Position of usage in the eviction order, None if the policy don't need one
+    fn rank(policy: EvictionPolicy, usage: &Usage) -> Option<(u64, u64)> {
+        match policy {
+            EvictionPolicy::Lru => Some((usage.last_used, 0)),
+            EvictionPolicy::Lfu => Some((usage.uses, usage.last_used)),
+            _ => None,
+        }
+    }
+
+    fn insert(&mut self, policy: EvictionPolicy, key: &[u8], usage: Usage) {
+        if let Some((a, b)) = Tracker::rank(policy, &usage) {
+            self.order.insert((a, b, key.into()));
+        }
+        self.usage.insert(key.into(), usage);
+    }
+
+    fn remove(&mut self, policy: EvictionPolicy, key: &[u8]) -> Option<Usage> {
+        let usage = self.usage.remove(key)?;
+        if let Some((a, b)) = Tracker::rank(policy, &usage) {
+            self.order.remove(&(a, b, key.into()));
+        }
+
+        Some(usage)
+    }
+}
+
+impl Cache {
+    fn new(config: CacheConfig) -> Self {
+        Cache {
+            config,
+            bytes: 0,
+            evictions: 0,
+            tracker: Mutex::new(Tracker::default()),
+        }
+    }
+
+    fn tracker(&mut self) -> &mut Tracker {
+        self.tracker.get_mut().unwrap()
+    }
+
+    /// Mark key as used
+    pub fn touch(&self, key: &[u8]) {
+        let mut tracker = self.tracker.lock().unwrap();
+        if let Some(mut usage) = tracker.remove(self.config.policy, key) {
+            usage.last_used = tracker.tick();
+            usage.uses += 1;
+            tracker.insert(self.config.policy, key, usage);
+        }
+    }
+
+    /// Account the new value of key, None if it has been removed
+    pub fn update(&mut self, key: &[u8], value: Option<&BvObject>) {
+        let policy = self.config.policy;
+        let old = self.tracker().remove(policy, key);
+        if let Some(usage) = old.as_ref() {
+            self.bytes -= usage.size;
+        }
+
+        if let Some(value) = value {
+            self.insert(key, value, old.map_or(1, |usage| usage.uses + 1));
+        }
+    }
+
+    fn insert(&mut self, key: &[u8], value: &BvObject, uses: u64) {
+        let policy = self.config.policy;
+        let size = entry_size(key, value);
+        self.bytes += size;
+
+        let tracker = self.tracker();
+        let usage = Usage {
+            size,
+            last_used: tracker.tick(),
+            uses,
+        };
+        tracker.insert(policy, key, usage);
+    }
+
+    fn clear(&mut self) {
+        self.bytes = 0;
+        *self.tracker() = Tracker::default();
+    }
+
+    fn keys(&self) -> usize {
+        self.tracker.lock().unwrap().usage.len()
+    }
+
+    fn over_budget(&self) -> bool {
+        let exceeds = |used: usize, max: Option<usize>| match max {
+            Some(max) => used > max,
+            None => false,
+        };
+
+        exceeds(self.bytes, self.config.max_bytes) || exceeds(self.keys(), self.config.max_keys)
+    }
+
+    /// Key to evict according to the policy, never `written`
+    fn victim<'a>(
+        &self,
+        written: &[u8],
+        mut expiring: impl Iterator<Item = &'a BvString>,
+    ) -> Option<BvString> {
+        let tracker = self.tracker.lock().unwrap();
+
+        match self.config.policy {
+            EvictionPolicy::Lru | EvictionPolicy::Lfu => tracker
+                .order
+                .iter()
+                .map(|(_, _, k)| k)
+                .find(|k| k.as_slice() != written)
+                .cloned(),
+            EvictionPolicy::Random => {
+                let len = tracker.usage.len() - tracker.usage.contains_key(written) as usize;
+                if len == 0 {
+                    return None;
+                }
+
+                let mut hasher = RandomState::new().build_hasher();
+                hasher.write_u64(tracker.clock);
+                tracker
+                    .usage
+                    .keys()
+                    .filter(|k| k.as_slice() != written)
+                    .nth(hasher.finish() as usize % len)
+                    .cloned()
+            }
+            EvictionPolicy::VolatileTtl => expiring.find(|k| k.as_slice() != written).cloned(),
+        }
+    }
+}
+
+/// Byte length of key, type name and value
+pub fn entry_size(key: &[u8], value: &BvObject) -> usize {
+    key.len() + value.type_name().len() + value.len()
+}
+
+impl<KV> KvDb<KV>
+where
+    KV: KvInterface<Key = BvString, Value = BvObject, RefKey = [u8]>,
+{
+    /// Count a read of key as use
+    pub(super) fn touch(&self, key: &[u8]) {
+        if let Some(cache) = self.cache.as_ref() {
+            cache.touch(key);
+        }
+    }
+
+    /// Account all records from scratch
+    pub(super) fn rebuild_cache(&mut self) {
+        if let Some(cache) = self.cache.as_mut() {
+            cache.clear();
+            for (key, value) in self.records.iter_after(None) {
+                cache.insert(key.as_slice(), value, 1);
+            }
+        }
+    }
+}
+
+impl<KV> KvDb<KV>
+where
+    KV: KvInterface<Key = BvString, Value = BvObject, RefKey = [u8]>,
+    for<'a> &'a KV: IntoIterator<Item = (&'a BvString, &'a BvObject)>,
+{
+    /// Turn on cache mode, keys are evicted right away if the database exceeds the budget
+    ///
+    /// Replaces the previous configuration, usage statistics restart.
+    pub fn set_cache(&mut self, config: CacheConfig) {
+        self.cache = Some(Cache::new(config));
+        self.rebuild_cache();
+        self.evict(&[]);
+    }
+
+    /// Turn off cache mode, returns false if it wasn't on
+    ///
+    pub fn remove_cache(&mut self) -> bool {
+        self.cache.take().is_some()
+    }
+
+    pub fn cache_config(&self) -> Option<&CacheConfig> {
+        self.cache.as_ref().map(|c| &c.config)
+    }
+
+    /// Bytes, keys and evictions so far, None if cache mode is off
+    ///
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(|c| CacheStats {
+            bytes: c.bytes,
+            keys: c.keys(),
+            evictions: c.evictions,
+        })
+    }
+
+    /// Err if value alone exceeds the byte budget
+    pub(super) fn check_cache_budget(&self, key: &[u8], value: &BvObject) -> Result<(), String> {
+        let max_bytes = match self.cache.as_ref().and_then(|c| c.config.max_bytes) {
+            Some(max_bytes) => max_bytes,
+            None => return Ok(()),
+        };
+
+        let size = entry_size(key, value);
+        if size > max_bytes {
+            return Err(format!(
+                "Entry of {} bytes exceeds the cache budget of {} bytes",
+                size, max_bytes
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Evict keys until the database is within budget
+    pub(super) fn evict(&mut self, written: &[u8]) {
+        loop {
+            let victim = match self.cache.as_ref() {
+                Some(cache) if cache.over_budget() => {
+                    cache.victim(written, self.expiry.expired(u64::MAX))
+                }
+                _ => return,
+            };
+
+            match victim {
+                Some(key) => {
+                    if self.discard(key.as_slice(), EventKind::Evict) {
+                        self.cache.as_mut().unwrap().evictions += 1;
+                    } else if let Some(cache) = self.cache.as_mut() {
+                        // Stale entry without a record, drop it so the loop can progress
+                        cache.update(key.as_slice(), None);
+                    }
+                }
+                None => return,
+            }
+        }
+    }
+}
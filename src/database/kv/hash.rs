@@ -0,0 +1,199 @@
+//! Hash operations on KvDb
+//!
+//! Hashes are stored as ordinary records of type "hash", see [BvHash](../../../types/bv/bvhash/struct.BvHash.html)
+
+use std::convert::TryFrom;
+
+use super::types::Op;
+use super::KvDb;
+use crate::slice::glob_match;
+use crate::storage::KvInterface;
+use crate::types::bv::{bvhash, elements, BvHash, BvObject, BvString};
+use crate::utils::serialize_object;
+
+impl<KV> KvDb<KV>
+where
+    KV: KvInterface<Key = BvString, Value = BvObject, RefKey = [u8]>,
+    for<'a> &'a KV: IntoIterator<Item = (&'a BvString, &'a BvObject)>,
+{
+    /// Set field of a hash, the hash is created if the key don't exists
+    ///
+    /// Returns false if the field already existed
+    pub fn hset<S: AsRef<str>, F: AsRef<str>, T: serde::Serialize>(
+        &mut self,
+        key: S,
+        field: F,
+        value: T,
+    ) -> Result<bool, String> {
+        let value = serialize_object(&value);
+        self.hset_object(Op::HSet, key.as_ref().as_bytes(), field.as_ref(), value)
+    }
+
+    /// Value of field, None if the field or key don't exists
+    ///
+    pub fn hget<S: AsRef<str>, F: AsRef<str>>(
+        &self,
+        key: S,
+        field: F,
+    ) -> Result<Option<BvObject>, String> {
+        Ok(self
+            .get_typed(key.as_ref().as_bytes(), bvhash::HASH_TYPE)?
+            .and_then(|hash| bvhash::get(hash, field.as_ref())))
+    }
+
+    /// Remove field from a hash, the key is deleted when the hash is empty
+    ///
+    /// Returns false if the field don't exists
+    pub fn hdel<S: AsRef<str>, F: AsRef<str>>(&mut self, key: S, field: F) -> Result<bool, String> {
+        let (key, field) = (key.as_ref().as_bytes(), field.as_ref());
+
+        let len = match self.get_typed(key, bvhash::HASH_TYPE)? {
+            Some(hash) if bvhash::contains(hash, field) => elements::count(hash.as_slice()),
+            _ => return Ok(false),
+        };
+
+        if len == 1 {
+            self.apply(Op::HDel, key, None);
+        } else {
+            self.modify_checked(Op::HDel, key, |hash| {
+                BvHash::from(hash).unwrap().remove(field)
+            })?;
+        }
+
+        Ok(true)
+    }
+
+    /// Check if field exists in a hash
+    ///
+    pub fn hexists<S: AsRef<str>, F: AsRef<str>>(&self, key: S, field: F) -> Result<bool, String> {
+        Ok(self
+            .get_typed(key.as_ref().as_bytes(), bvhash::HASH_TYPE)?
+            .map(|hash| bvhash::contains(hash, field.as_ref()))
+            .unwrap_or(false))
+    }
+
+    /// All fields and values of a hash, ordered by field name
+    ///
+    pub fn hgetall<S: AsRef<str>>(&self, key: S) -> Result<Vec<(String, BvObject)>, String> {
+        Ok(
+            match self.get_typed(key.as_ref().as_bytes(), bvhash::HASH_TYPE)? {
+                Some(hash) => bvhash::entries(hash),
+                None => Vec::new(),
+            },
+        )
+    }
+
+    /// All field names of a hash, sorted
+    ///
+    pub fn hkeys<S: AsRef<str>>(&self, key: S) -> Result<Vec<String>, String> {
+        Ok(
+            match self.get_typed(key.as_ref().as_bytes(), bvhash::HASH_TYPE)? {
+                Some(hash) => bvhash::fields(hash),
+                None => Vec::new(),
+            },
+        )
+    }
+
+    /// Number of fields in a hash, 0 if the key don't exists
+    ///
+    pub fn hlen<S: AsRef<str>>(&self, key: S) -> Result<usize, String> {
+        Ok(self
+            .get_typed(key.as_ref().as_bytes(), bvhash::HASH_TYPE)?
+            .map(|hash| elements::count(hash.as_slice()))
+            .unwrap_or(0))
+    }
+
+    /// Increment an integer field, i64 is used if the field don't exists
+    ///
+    /// The field keeps its integer type, returns the new value
+    pub fn hincrby<S: AsRef<str>, F: AsRef<str>>(
+        &mut self,
+        key: S,
+        field: F,
+        increment: i64,
+    ) -> Result<i128, String> {
+        let (key, field) = (key.as_ref().as_bytes(), field.as_ref());
+
+        let (value, n) = match self.get_typed(key, bvhash::HASH_TYPE)? {
+            Some(hash) => match bvhash::get(hash, field) {
+                Some(value) => add_int(&value, increment).ok_or_else(|| {
+                    format!("Field \"{}\" is not an integer or overflowed", field)
+                })?,
+                None => (serialize_object(&increment), increment as i128),
+            },
+            None => (serialize_object(&increment), increment as i128),
+        };
+
+        self.hset_object(Op::HIncrBy, key, field, value)?;
+        Ok(n)
+    }
+
+    /// Fields and values of a hash with names matching a glob style pattern, ordered by field name
+    ///
+    /// `*` matches any sequence, `?` any single character and `[a-z]` a set of characters.
+    pub fn hscan<S: AsRef<str>, P: AsRef<str>>(
+        &self,
+        key: S,
+        pattern: P,
+    ) -> Result<Vec<(String, BvObject)>, String> {
+        let pattern = pattern.as_ref().as_bytes();
+        Ok(self
+            .hgetall(key)?
+            .into_iter()
+            .filter(|(field, _)| glob_match(field.as_bytes(), pattern))
+            .collect())
+    }
+
+    fn hset_object(
+        &mut self,
+        op: Op,
+        key: &[u8],
+        field: &str,
+        value: BvObject,
+    ) -> Result<bool, String> {
+        match self.get_typed(key, bvhash::HASH_TYPE)? {
+            Some(hash) if is_same(bvhash::get(hash, field), &value) => Ok(false),
+            Some(_) => Ok(self
+                .modify_checked(op, key, |hash| {
+                    BvHash::from(hash).unwrap().insert(field, &value)
+                })?
+                .unwrap()),
+            None => {
+                let mut hash = BvHash::new_object();
+                BvHash::from(&mut hash).unwrap().insert(field, &value);
+                self.apply_checked(op, key, Some(hash))?;
+                Ok(true)
+            }
+        }
+    }
+}
+
+/// Check if old holds value, the same bytes of another type is a different value
+fn is_same(old: Option<BvObject>, value: &BvObject) -> bool {
+    matches!(old, Some(old) if old.type_name() == value.type_name() && old == *value)
+}
+
+/// Add to an integer BvObject keeping its type, None on overflow or if value isn't an integer
+fn add_int(value: &BvObject, increment: i64) -> Option<(BvObject, i128)> {
+    macro_rules! add {
+        ($t:ty) => {{
+            let v = i128::try_from(value.extract::<$t>()).ok()?;
+            let n = v.checked_add(increment as i128)?;
+            Some((serialize_object(&<$t>::try_from(n).ok()?), n))
+        }};
+    }
+
+    match value.type_name().as_str() {
+        "i8" => add!(i8),
+        "i16" => add!(i16),
+        "i32" => add!(i32),
+        "i64" => add!(i64),
+        "i128" => add!(i128),
+        "u8" => add!(u8),
+        "u16" => add!(u16),
+        "u32" => add!(u32),
+        "u64" => add!(u64),
+        "u128" => add!(u128),
+        _ => None,
+    }
+}
@@ -0,0 +1,83 @@
+//! HyperLogLog operations on KvDb
+//!
+//! HyperLogLogs are stored as ordinary records of type "hll", see [bvhll](../../../types/bv/bvhll/index.html)
+//! for the error bound.
+
+use super::types::Op;
+use super::KvDb;
+use crate::storage::KvInterface;
+use crate::types::bv::{bvhll, BvHll, BvObject, BvString};
+use crate::utils::serialize;
+
+impl<KV> KvDb<KV>
+where
+    KV: KvInterface<Key = BvString, Value = BvObject, RefKey = [u8]>,
+    for<'a> &'a KV: IntoIterator<Item = (&'a BvString, &'a BvObject)>,
+{
+    /// Count elements in a HyperLogLog, the HyperLogLog is created if the key don't exists
+    ///
+    /// Returns true if the estimated cardinality may have changed
+    pub fn pfadd<S: AsRef<str>, T: serde::Serialize>(
+        &mut self,
+        key: S,
+        elements: &[T],
+    ) -> Result<bool, String> {
+        let key = key.as_ref().as_bytes();
+        let elements = elements.iter().map(serialize).collect::<Vec<_>>();
+        let add = |hll: &mut BvObject| {
+            let mut hll = BvHll::from(hll)?;
+            let mut changed = false;
+            for e in &elements {
+                changed |= hll.add(e);
+            }
+            Ok(changed)
+        };
+
+        match self.get_typed(key, bvhll::HLL_TYPE)? {
+            Some(_) => self.modify_checked(Op::PfAdd, key, add)?.unwrap(),
+            None => {
+                let mut hll = BvHll::new_object();
+                add(&mut hll)?;
+                self.apply_checked(Op::PfAdd, key, Some(hll))?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Estimated number of unique elements counted in the union of HyperLogLogs,
+    /// missing keys are treated as empty
+    ///
+    pub fn pfcount<S: AsRef<str>>(&self, keys: &[S]) -> Result<u64, String> {
+        let mut union = BvHll::new_bytevec();
+        let mut merged = BvHll::from(&mut union).unwrap();
+
+        for key in keys {
+            if let Some(hll) = self.get_typed(key.as_ref().as_bytes(), bvhll::HLL_TYPE)? {
+                merged.merge(hll.as_slice());
+            }
+        }
+
+        Ok(merged.count())
+    }
+
+    /// Merge HyperLogLogs into destination, existing registers of destination are kept
+    ///
+    pub fn pfmerge<S: AsRef<str>>(&mut self, destination: S, keys: &[S]) -> Result<(), String> {
+        let destination = destination.as_ref().as_bytes();
+
+        let mut union = match self.get_typed(destination, bvhll::HLL_TYPE)? {
+            Some(hll) => hll.clone(),
+            None => BvHll::new_object(),
+        };
+        let mut merged = BvHll::from(&mut union)?;
+
+        for key in keys {
+            if let Some(hll) = self.get_typed(key.as_ref().as_bytes(), bvhll::HLL_TYPE)? {
+                merged.merge(hll.as_slice());
+            }
+        }
+
+        self.apply_checked(Op::PfMerge, destination, Some(union))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,590 @@
+//! Secondary indexes on KvDb values
+//!
+//! An index covers every key starting with its prefix and maps the indexed part of the
+//! value to the keys holding it. Indexes on the whole value are rebuilt from the records
+//! on open. Field indexes are maintained by writes of typed values(`set`, `set_ex`, `swap`,
+//! `store`), their entries are written to the database file since bincode encoded structs
+//! can't be read without their type. Other writes to a key remove it from field indexes.
+//!
+//! Values are compared by an order preserving sort key, integers are compared as numbers
+//! regardless of their width, strings by their bytes. Integers and floats are not compared
+//! with each other.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Bound;
+
+use serde::ser::{self, Impossible, Serialize, Serializer};
+
+use super::types::{Extractor, IndexDef, IndexEntries};
+use super::KvDb;
+use crate::storage::KvInterface;
+use crate::types::bv::{BvObject, BvString};
+use crate::utils::{serialize_object, type_name_of};
+
+#[derive(Clone)]
+pub struct SecondaryIndex {
+    def: IndexDef,
+    entries: BTreeMap<Vec<u8>, BTreeSet<BvString>>,
+    keys: BTreeMap<BvString, Vec<u8>>,
+}
+
+impl SecondaryIndex {
+    fn new(def: IndexDef) -> Self {
+        SecondaryIndex {
+            def,
+            entries: BTreeMap::new(),
+            keys: BTreeMap::new(),
+        }
+    }
+
+    pub fn def(&self) -> &IndexDef {
+        &self.def
+    }
+
+    fn covers(&self, key: &[u8]) -> bool {
+        key.starts_with(self.def.prefix.as_bytes())
+    }
+
+    fn insert(&mut self, key: &[u8], sort_key: Vec<u8>) {
+        self.remove(key);
+        self.entries
+            .entry(sort_key.clone())
+            .or_default()
+            .insert(key.into());
+        self.keys.insert(key.into(), sort_key);
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        if let Some(sort_key) = self.keys.remove(key) {
+            let keys = self.entries.get_mut(&sort_key).unwrap();
+            keys.remove(key);
+            if keys.is_empty() {
+                self.entries.remove(&sort_key);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.keys.clear();
+    }
+}
+
+/// Update the value indexes covering key and remove key from field indexes
+pub fn update(
+    indexes: &mut BTreeMap<String, SecondaryIndex>,
+    key: &[u8],
+    value: Option<&BvObject>,
+) {
+    for index in indexes.values_mut().filter(|i| i.covers(key)) {
+        match (&index.def.extractor, value) {
+            (Extractor::Value, Some(value)) => index.insert(key, sort_key(value)),
+            _ => index.remove(key),
+        }
+    }
+}
+
+/// Update the field indexes covering key with the fields of value
+pub fn update_fields<T: Serialize + ?Sized>(
+    indexes: &mut BTreeMap<String, SecondaryIndex>,
+    key: &[u8],
+    value: &T,
+) {
+    for index in indexes.values_mut().filter(|i| i.covers(key)) {
+        if let Extractor::Field(field) = &index.def.extractor {
+            if let Some(field) = capture_field(value, field) {
+                index.insert(key, sort_key(&field));
+            }
+        }
+    }
+}
+
+/// Sort keys of key in field indexes, by index name
+pub fn field_keys(
+    indexes: &BTreeMap<String, SecondaryIndex>,
+    key: &[u8],
+) -> Vec<(String, Vec<u8>)> {
+    indexes
+        .values()
+        .filter(|i| i.def.extractor != Extractor::Value)
+        .filter_map(|i| i.keys.get(key).map(|s| (i.def.name.clone(), s.clone())))
+        .collect()
+}
+
+/// Insert key with sort keys from `field_keys` into the named indexes covering it
+pub fn insert_fields(
+    indexes: &mut BTreeMap<String, SecondaryIndex>,
+    key: &[u8],
+    field_keys: &[(String, Vec<u8>)],
+) {
+    for (name, sort_key) in field_keys {
+        if let Some(index) = indexes.get_mut(name).filter(|i| i.covers(key)) {
+            index.insert(key, sort_key.clone());
+        }
+    }
+}
+
+/// Index definitions and the entries of field indexes
+pub fn export(indexes: &BTreeMap<String, SecondaryIndex>) -> IndexEntries {
+    indexes
+        .values()
+        .map(|index| {
+            let entries = match index.def.extractor {
+                Extractor::Value => Vec::new(),
+                Extractor::Field(_) => index
+                    .keys
+                    .iter()
+                    .map(|(k, s)| (k.clone(), s.clone()))
+                    .collect(),
+            };
+
+            (index.def.clone(), entries)
+        })
+        .collect()
+}
+
+/// Restore exported indexes, value indexes have to be rebuilt
+pub fn import(exported: IndexEntries) -> BTreeMap<String, SecondaryIndex> {
+    exported
+        .into_iter()
+        .map(|(def, entries)| {
+            let mut index = SecondaryIndex::new(def);
+            for (key, sort_key) in entries {
+                index.insert(key.as_slice(), sort_key);
+            }
+
+            (index.def.name.clone(), index)
+        })
+        .collect()
+}
+
+impl<KV> KvDb<KV>
+where
+    KV: KvInterface<Key = BvString, Value = BvObject, RefKey = [u8]>,
+{
+    /// Rebuild indexes on the whole value from the records
+    pub(super) fn rebuild_value_indexes(&mut self) {
+        for index in self.indexes.values_mut() {
+            if index.def.extractor != Extractor::Value {
+                continue;
+            }
+
+            index.clear();
+            let prefix = index.def.prefix.clone();
+            for (key, value) in self
+                .records
+                .iter_after(None)
+                .skip_while(|(k, _)| !k.as_slice().starts_with(prefix.as_bytes()))
+                .take_while(|(k, _)| k.as_slice().starts_with(prefix.as_bytes()))
+            {
+                index.insert(key.as_slice(), sort_key(value));
+            }
+        }
+    }
+
+    /// Create an index on the whole values of keys starting with prefix
+    ///
+    pub fn create_index<N: AsRef<str>, P: AsRef<str>>(
+        &mut self,
+        name: N,
+        prefix: P,
+    ) -> Result<(), String> {
+        self.add_index(IndexDef {
+            name: name.as_ref().to_string(),
+            prefix: prefix.as_ref().to_string(),
+            extractor: Extractor::Value,
+        })?;
+
+        self.rebuild_value_indexes();
+        Ok(())
+    }
+
+    /// Create an index on a named field of structs of type T stored under keys starting with prefix
+    ///
+    /// Existing values of type T are indexed, values which can't be decoded as T are skipped.
+    pub fn create_field_index<T, N, P, F>(
+        &mut self,
+        name: N,
+        prefix: P,
+        field: F,
+    ) -> Result<(), String>
+    where
+        T: Serialize + serde::de::DeserializeOwned,
+        N: AsRef<str>,
+        P: AsRef<str>,
+        F: AsRef<str>,
+    {
+        let name = name.as_ref().to_string();
+        self.add_index(IndexDef {
+            name: name.clone(),
+            prefix: prefix.as_ref().to_string(),
+            extractor: Extractor::Field(field.as_ref().to_string()),
+        })?;
+
+        let type_name = type_name_of::<T>();
+        let values = self
+            .records
+            .iter_after(None)
+            .filter(|(k, v)| {
+                k.as_slice().starts_with(prefix.as_ref().as_bytes()) && v.type_name() == type_name
+            })
+            .filter_map(|(k, v)| {
+                Some((
+                    k.clone(),
+                    bincode::deserialize::<T>(v.raw().as_slice()).ok()?,
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        for (key, value) in values {
+            update_fields(&mut self.indexes, key.as_slice(), &value);
+        }
+
+        Ok(())
+    }
+
+    /// Remove an index, returns false if it don't exists
+    ///
+    pub fn drop_index<N: AsRef<str>>(&mut self, name: N) -> bool {
+        self.indexes.remove(name.as_ref()).is_some()
+    }
+
+    /// Definitions of all indexes
+    ///
+    pub fn indexes(&self) -> Vec<&IndexDef> {
+        self.indexes.values().map(|i| &i.def).collect()
+    }
+
+    /// Query an index
+    ///
+    /// ```
+    /// use icbiadb::storage::BTreeMap;
+    ///
+    /// let mut db = icbiadb::kv::mem::<BTreeMap>();
+    /// db.create_index("by_name", "user:").unwrap();
+    /// db.set("user:0", "alice").unwrap();
+    ///
+    /// assert_eq!(db.index("by_name").unwrap().get("alice").len(), 1);
+    /// ```
+    pub fn index<N: AsRef<str>>(&self, name: N) -> Result<IndexView<'_, KV>, String> {
+        match self.indexes.get(name.as_ref()) {
+            Some(index) => Ok(IndexView { db: self, index }),
+            None => Err(format!("Index \"{}\" does not exist", name.as_ref())),
+        }
+    }
+
+    fn add_index(&mut self, def: IndexDef) -> Result<(), String> {
+        if self.indexes.contains_key(&def.name) {
+            return Err(format!("Index \"{}\" already exists", def.name));
+        }
+
+        self.indexes
+            .insert(def.name.clone(), SecondaryIndex::new(def));
+        Ok(())
+    }
+}
+
+/// Lookups on a secondary index
+pub struct IndexView<'a, KV: KvInterface> {
+    db: &'a KvDb<KV>,
+    index: &'a SecondaryIndex,
+}
+
+impl<'a, KV> IndexView<'a, KV>
+where
+    KV: KvInterface<Key = BvString, Value = BvObject, RefKey = [u8]>,
+{
+    pub fn def(&self) -> &IndexDef {
+        &self.index.def
+    }
+
+    /// Number of indexed keys
+    pub fn len(&self) -> usize {
+        self.index.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.keys.is_empty()
+    }
+
+    /// Records with an indexed value equal to value, sorted by key
+    pub fn get<T: Serialize>(&self, value: T) -> Vec<(&'a BvString, &'a BvObject)> {
+        let sort_key = sort_key(&serialize_object(&value));
+        self.records(
+            self.index
+                .entries
+                .range::<Vec<u8>, _>((Bound::Included(&sort_key), Bound::Included(&sort_key))),
+        )
+    }
+
+    /// Records with min <= indexed value <= max, ordered by indexed value
+    pub fn range<T: Serialize>(&self, min: T, max: T) -> Vec<(&'a BvString, &'a BvObject)> {
+        let (min, max) = (
+            sort_key(&serialize_object(&min)),
+            sort_key(&serialize_object(&max)),
+        );
+
+        if min > max {
+            return Vec::new();
+        }
+
+        self.records(
+            self.index
+                .entries
+                .range::<Vec<u8>, _>((Bound::Included(&min), Bound::Included(&max))),
+        )
+    }
+
+    fn records<'b, I>(&self, entries: I) -> Vec<(&'a BvString, &'a BvObject)>
+    where
+        I: Iterator<Item = (&'b Vec<u8>, &'a BTreeSet<BvString>)>,
+    {
+        let db = self.db;
+        entries
+            .flat_map(|(_, keys)| keys.iter())
+            .filter(|k| !db.is_expired(k.as_slice()))
+            .filter_map(|k| db.records.get(k.as_slice()).map(|v| (k, v)))
+            .collect()
+    }
+}
+
+/// Order preserving bytes of a value, prefixed by the kind of value
+pub fn sort_key(v: &BvObject) -> Vec<u8> {
+    let mut key = Vec::with_capacity(v.len() + 2);
+
+    match v.type_name().as_str() {
+        "bool" => {
+            key.push(0);
+            key.push(v[0]);
+        }
+        "i8" | "i16" | "i32" | "i64" | "i128" | "u8" | "u16" | "u32" | "u64" | "u128" => {
+            let n = match v.type_name().as_str() {
+                "i8" => v.as_i8() as i128,
+                "i16" => v.as_i16() as i128,
+                "i32" => v.as_i32() as i128,
+                "i64" => v.as_i64() as i128,
+                "i128" => v.as_i128(),
+                "u8" => v.as_u8() as i128,
+                "u16" => v.as_u16() as i128,
+                "u32" => v.as_u32() as i128,
+                "u64" => v.as_u64() as i128,
+                _ => 0,
+            };
+            // Negative numbers sort before all others, u128 doesn't fit in i128
+            let (sign, bits) = match v.type_name().as_str() {
+                "u128" => (1, v.as_u128()),
+                _ if n < 0 => (0, n as u128),
+                _ => (1, n as u128),
+            };
+            key.push(1);
+            key.push(sign);
+            key.extend_from_slice(&bits.to_be_bytes());
+        }
+        "f64" => {
+            let f = if v.len() == 4 {
+                v.as_f32() as f64
+            } else {
+                v.as_f64()
+            };
+            let bits = f.to_bits();
+            let bits = if bits >> 63 == 1 {
+                !bits
+            } else {
+                bits | (1 << 63)
+            };
+            key.push(2);
+            key.extend_from_slice(&bits.to_be_bytes());
+        }
+        "str" => {
+            key.push(3);
+            key.extend_from_slice(v.as_slice().get(8..).unwrap_or_default());
+        }
+        "char" => {
+            key.push(3);
+            key.extend_from_slice(v.as_slice());
+        }
+        _ => {
+            key.push(4);
+            key.extend_from_slice(v.type_name().as_slice());
+            key.push(0);
+            key.extend_from_slice(v.as_slice());
+        }
+    }
+
+    key
+}
+
+/// Serialize value and capture the named field if value is a struct
+pub fn capture_field<T: Serialize + ?Sized>(value: &T, field: &str) -> Option<BvObject> {
+    let mut capture = FieldCapture { field, found: None };
+    value.serialize(&mut capture).ok()?;
+    capture.found
+}
+
+struct FieldCapture<'a> {
+    field: &'a str,
+    found: Option<BvObject>,
+}
+
+#[derive(Debug)]
+struct NotAStruct;
+
+impl std::fmt::Display for NotAStruct {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Not a struct")
+    }
+}
+
+impl std::error::Error for NotAStruct {}
+
+impl ser::Error for NotAStruct {
+    fn custom<T: std::fmt::Display>(_: T) -> Self {
+        NotAStruct
+    }
+}
+
+macro_rules! not_a_struct {
+    ($($f:ident: $t:ty),*) => {
+        $(fn $f(self, _: $t) -> Result<(), NotAStruct> {
+            Err(NotAStruct)
+        })*
+    };
+}
+
+impl<'a, 'b> Serializer for &'b mut FieldCapture<'a> {
+    type Ok = ();
+    type Error = NotAStruct;
+    type SerializeSeq = Impossible<(), NotAStruct>;
+    type SerializeTuple = Impossible<(), NotAStruct>;
+    type SerializeTupleStruct = Impossible<(), NotAStruct>;
+    type SerializeTupleVariant = Impossible<(), NotAStruct>;
+    type SerializeMap = Impossible<(), NotAStruct>;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), NotAStruct>;
+
+    not_a_struct!(
+        serialize_bool: bool,
+        serialize_i8: i8,
+        serialize_i16: i16,
+        serialize_i32: i32,
+        serialize_i64: i64,
+        serialize_u8: u8,
+        serialize_u16: u16,
+        serialize_u32: u32,
+        serialize_u64: u64,
+        serialize_f32: f32,
+        serialize_f64: f64,
+        serialize_char: char,
+        serialize_str: &str,
+        serialize_bytes: &[u8],
+        serialize_unit_struct: &'static str
+    );
+
+    fn serialize_none(self) -> Result<(), NotAStruct> {
+        Err(NotAStruct)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), NotAStruct> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), NotAStruct> {
+        Err(NotAStruct)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+    ) -> Result<(), NotAStruct> {
+        Err(NotAStruct)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), NotAStruct> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<(), NotAStruct> {
+        Err(NotAStruct)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, NotAStruct> {
+        Err(NotAStruct)
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, NotAStruct> {
+        Err(NotAStruct)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, NotAStruct> {
+        Err(NotAStruct)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, NotAStruct> {
+        Err(NotAStruct)
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, NotAStruct> {
+        Err(NotAStruct)
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct, NotAStruct> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, NotAStruct> {
+        Err(NotAStruct)
+    }
+}
+
+impl<'a, 'b> ser::SerializeStruct for &'b mut FieldCapture<'a> {
+    type Ok = ();
+    type Error = NotAStruct;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), NotAStruct> {
+        if key == self.field {
+            self.found = Some(serialize_object(value));
+        }
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), NotAStruct> {
+        Ok(())
+    }
+}
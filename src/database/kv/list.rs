@@ -0,0 +1,167 @@
+//! List operations on KvDb
+//!
+//! Lists are stored as ordinary records of type "list", see [BvList](../../../types/bv/bvlist/struct.BvList.html)
+
+use super::types::Op;
+use super::KvDb;
+use crate::storage::KvInterface;
+use crate::types::bv::{bvlist, BvList, BvObject, BvString};
+use crate::utils::serialize_object;
+
+impl<KV> KvDb<KV>
+where
+    KV: KvInterface<Key = BvString, Value = BvObject, RefKey = [u8]>,
+    for<'a> &'a KV: IntoIterator<Item = (&'a BvString, &'a BvObject)>,
+{
+    /// Prepend value to a list, the list is created if the key don't exists
+    ///
+    /// Returns the length of the list
+    pub fn lpush<S: AsRef<str>, T: serde::Serialize>(
+        &mut self,
+        key: S,
+        value: T,
+    ) -> Result<usize, String> {
+        self.push(Op::LPush, key.as_ref().as_bytes(), serialize_object(&value))
+    }
+
+    /// Append value to a list, the list is created if the key don't exists
+    ///
+    /// Returns the length of the list
+    pub fn rpush<S: AsRef<str>, T: serde::Serialize>(
+        &mut self,
+        key: S,
+        value: T,
+    ) -> Result<usize, String> {
+        self.push(Op::RPush, key.as_ref().as_bytes(), serialize_object(&value))
+    }
+
+    /// Remove and return the first element, the key is deleted when the list is empty
+    ///
+    pub fn lpop<S: AsRef<str>>(&mut self, key: S) -> Result<Option<BvObject>, String> {
+        self.pop(Op::LPop, key.as_ref().as_bytes())
+    }
+
+    /// Remove and return the last element, the key is deleted when the list is empty
+    ///
+    pub fn rpop<S: AsRef<str>>(&mut self, key: S) -> Result<Option<BvObject>, String> {
+        self.pop(Op::RPop, key.as_ref().as_bytes())
+    }
+
+    /// Elements from start to stop(inclusive), negative indexes count from the end
+    ///
+    pub fn lrange<S: AsRef<str>>(
+        &self,
+        key: S,
+        start: isize,
+        stop: isize,
+    ) -> Result<Vec<BvObject>, String> {
+        match self.get_typed(key.as_ref().as_bytes(), bvlist::LIST_TYPE)? {
+            Some(list) => Ok(bvlist::range(list, start, stop)),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Element at index, negative indexes count from the end
+    ///
+    pub fn lindex<S: AsRef<str>>(&self, key: S, index: isize) -> Result<Option<BvObject>, String> {
+        match self.get_typed(key.as_ref().as_bytes(), bvlist::LIST_TYPE)? {
+            Some(list) => Ok(bvlist::get(list, index)),
+            None => Ok(None),
+        }
+    }
+
+    /// Overwrite the element at index, the value may differ in type and length
+    ///
+    pub fn lset<S: AsRef<str>, T: serde::Serialize>(
+        &mut self,
+        key: S,
+        index: isize,
+        value: T,
+    ) -> Result<(), String> {
+        let key = key.as_ref().as_bytes();
+        if self.get_typed(key, bvlist::LIST_TYPE)?.is_none() {
+            return Err(format!("No such key \"{}\"", String::from_utf8_lossy(key)));
+        }
+
+        let value = serialize_object(&value);
+        self.modify_checked(Op::LSet, key, |list| {
+            BvList::from(list).unwrap().set(index, &value)
+        })?
+        .unwrap()
+    }
+
+    /// Keep only the elements from start to stop(inclusive), the key is deleted when the list is empty
+    ///
+    pub fn ltrim<S: AsRef<str>>(
+        &mut self,
+        key: S,
+        start: isize,
+        stop: isize,
+    ) -> Result<(), String> {
+        let key = key.as_ref().as_bytes();
+        let len = match self.get_typed(key, bvlist::LIST_TYPE)? {
+            Some(list) => BvList::len_of(list),
+            None => return Ok(()),
+        };
+
+        if crate::types::bv::elements::normalize_range(start, stop, len).is_none() {
+            self.apply(Op::LTrim, key, None);
+        } else {
+            self.modify_checked(Op::LTrim, key, |list| {
+                BvList::from(list).unwrap().trim(start, stop)
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Length of a list, 0 if the key don't exists
+    ///
+    pub fn llen<S: AsRef<str>>(&self, key: S) -> Result<usize, String> {
+        Ok(self
+            .get_typed(key.as_ref().as_bytes(), bvlist::LIST_TYPE)?
+            .map(BvList::len_of)
+            .unwrap_or(0))
+    }
+
+    fn push(&mut self, op: Op, key: &[u8], value: BvObject) -> Result<usize, String> {
+        if self.get_typed(key, bvlist::LIST_TYPE)?.is_none() {
+            let mut list = BvList::new_object();
+            BvList::from(&mut list).unwrap().push_back(&value);
+            self.apply_checked(op, key, Some(list))?;
+            return Ok(1);
+        }
+
+        Ok(self
+            .modify_checked(op, key, |list| {
+                let mut list = BvList::from(list).unwrap();
+                match op {
+                    Op::LPush => list.push_front(&value),
+                    _ => list.push_back(&value),
+                }
+                list.len()
+            })?
+            .unwrap())
+    }
+
+    fn pop(&mut self, op: Op, key: &[u8]) -> Result<Option<BvObject>, String> {
+        let len = match self.get_typed(key, bvlist::LIST_TYPE)? {
+            Some(list) => BvList::len_of(list),
+            None => return Ok(None),
+        };
+
+        let index = match op {
+            Op::LPop => 0,
+            _ => -1,
+        };
+
+        if len <= 1 {
+            let list = self.apply(op, key, None).unwrap();
+            return Ok(bvlist::get(&list, index));
+        }
+
+        Ok(self
+            .modify_checked(op, key, |list| BvList::from(list).unwrap().remove(index))?
+            .unwrap())
+    }
+}
@@ -0,0 +1,176 @@
+//! Merge operators of KvDb, read-modify-write of a value in a single call
+//!
+//! Operators are registered by name and bound to keys with glob style patterns, `merge`
+//! applies the operator bound to the key to the current value and an operand. Built-in
+//! operators are always available:
+//! * `add`, numeric add, see [numeric](../../../types/bv/numeric/index.html)
+//! * `append`, string append
+//! * `union`, adds the members of a set operand, or any other operand as a member, to a set
+//! * `max`, numeric max
+//!
+//! Records live in memory and are written as a whole on commit, so merges are applied right
+//! away. There is no LSM tree or write-ahead log to defer them to. Operators and bindings
+//! are not written to the database file.
+
+use super::types::Op;
+use super::KvDb;
+use crate::slice::glob_match;
+use crate::storage::KvInterface;
+use crate::types::bv::{bvset, numeric, BvObject, BvSet, BvString};
+use crate::utils::serialize_object;
+
+/// Merge the current value, None if the key don't exists, with an operand
+pub type MergeFn =
+    Box<dyn Fn(Option<&BvObject>, &BvObject) -> Result<BvObject, String> + Send + Sync>;
+
+type BuiltinFn = fn(Option<&BvObject>, &BvObject) -> Result<BvObject, String>;
+
+fn builtin(name: &str) -> Option<BuiltinFn> {
+    match name {
+        "add" => Some(add),
+        "append" => Some(append),
+        "union" => Some(union),
+        "max" => Some(max),
+        _ => None,
+    }
+}
+
+/// Numeric add, a missing value is set to operand
+pub fn add(value: Option<&BvObject>, operand: &BvObject) -> Result<BvObject, String> {
+    match value {
+        Some(value) => value.checked_add(operand),
+        None => numeric::zero(operand)?.checked_add(operand),
+    }
+}
+
+/// String append, a missing value is set to operand
+pub fn append(value: Option<&BvObject>, operand: &BvObject) -> Result<BvObject, String> {
+    let expect_str = |v: &BvObject| {
+        if v.is_str() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Expected type \"str\" found type \"{}\"",
+                v.type_name()
+            ))
+        }
+    };
+
+    expect_str(operand)?;
+    match value {
+        Some(value) => {
+            expect_str(value)?;
+            Ok(serialize_object(
+                &[value.as_str(), operand.as_str()].concat(),
+            ))
+        }
+        None => Ok(operand.clone()),
+    }
+}
+
+/// Add the members of a set operand, or operand itself, to a set, a missing value is set to an empty set first
+pub fn union(value: Option<&BvObject>, operand: &BvObject) -> Result<BvObject, String> {
+    let mut set = match value {
+        Some(value) => value.clone(),
+        None => BvSet::new_object(),
+    };
+
+    let mut bv_set = BvSet::from(&mut set)?;
+    if operand.is_set() {
+        for member in bvset::members(operand) {
+            bv_set.insert(&member);
+        }
+    } else {
+        bv_set.insert(operand);
+    }
+
+    Ok(set)
+}
+
+/// Numeric max, a missing value is set to operand
+pub fn max(value: Option<&BvObject>, operand: &BvObject) -> Result<BvObject, String> {
+    match value {
+        Some(value) => value.num_max(operand),
+        None => operand.num_max(operand),
+    }
+}
+
+impl<KV> KvDb<KV>
+where
+    KV: KvInterface<Key = BvString, Value = BvObject, RefKey = [u8]>,
+    for<'a> &'a KV: IntoIterator<Item = (&'a BvString, &'a BvObject)>,
+{
+    /// Register a merge operator, replacing any operator with the same name including built-ins
+    ///
+    pub fn register_merge_operator<N, F>(&mut self, name: N, f: F)
+    where
+        N: AsRef<str>,
+        F: Fn(Option<&BvObject>, &BvObject) -> Result<BvObject, String> + Send + Sync + 'static,
+    {
+        self.merge_operators
+            .insert(name.as_ref().to_string(), Box::new(f));
+    }
+
+    /// Use the operator `name` to merge keys matching pattern, replacing the previous binding of pattern
+    ///
+    /// Err if no operator is named `name`. The first pattern bound is used for keys matching multiple patterns.
+    pub fn set_merge_operator<P: AsRef<str>, N: AsRef<str>>(
+        &mut self,
+        pattern: P,
+        name: N,
+    ) -> Result<(), String> {
+        let (pattern, name) = (pattern.as_ref(), name.as_ref());
+        if !self.merge_operators.contains_key(name) && builtin(name).is_none() {
+            return Err(format!("Merge operator \"{}\" does not exist", name));
+        }
+
+        match self.merge_bindings.iter_mut().find(|(p, _)| p == pattern) {
+            Some(binding) => binding.1 = name.to_string(),
+            None => self
+                .merge_bindings
+                .push((pattern.to_string(), name.to_string())),
+        }
+
+        Ok(())
+    }
+
+    /// Remove the binding of pattern, returns false if it don't exists
+    ///
+    pub fn remove_merge_operator<P: AsRef<str>>(&mut self, pattern: P) -> bool {
+        let len = self.merge_bindings.len();
+        self.merge_bindings.retain(|(p, _)| p != pattern.as_ref());
+        len != self.merge_bindings.len()
+    }
+
+    /// Merge operand into key with the operator bound to key and return the new value
+    ///
+    /// Err if no operator is bound to key, the operator fails or the new value violates a
+    /// key schema. Time to live of the key is kept.
+    pub fn merge<S: AsRef<str>, T: serde::Serialize>(
+        &mut self,
+        key: S,
+        operand: T,
+    ) -> Result<BvObject, String> {
+        let key = key.as_ref();
+        let name = match self
+            .merge_bindings
+            .iter()
+            .find(|(p, _)| glob_match(key.as_bytes(), p.as_bytes()))
+        {
+            Some((_, name)) => name,
+            None => return Err(format!("No merge operator is bound to key \"{}\"", key)),
+        };
+
+        let operand = serialize_object(&operand);
+        let value = self.get(key);
+        let merged = match (self.merge_operators.get(name), builtin(name)) {
+            (Some(f), _) => f(value, &operand)?,
+            (None, Some(f)) => f(value, &operand)?,
+            (None, None) => return Err(format!("Merge operator \"{}\" does not exist", name)),
+        };
+
+        self.apply_checked(Op::Merge, key.as_bytes(), Some(merged.clone()))?;
+
+        Ok(merged)
+    }
+}
@@ -6,24 +6,46 @@
 //!
 //! See [Storage](../../storage/index.html)
 
+pub mod bitmap;
+pub mod cache;
+pub mod hash;
+pub mod hll;
+pub mod index;
+pub mod list;
+pub mod merge;
+pub mod numeric;
 pub mod parser;
+pub mod pubsub;
+pub mod rename;
+pub mod rules;
+pub mod schema;
+pub mod set;
+pub mod stream;
+pub mod string;
+pub mod tree;
 pub mod types;
+pub mod zset;
 
+use std::collections::BTreeMap;
 use std::io::BufReader;
 
+use crate::database::journal::Journal;
+use crate::database::transaction::Savepoints;
 use crate::fio;
 use crate::prelude::*;
+use crate::slice::glob_match;
 use crate::storage::KvInterface;
 use crate::types::*;
-use crate::utils::{normalize_type_name, serialize, serialize_object};
+use crate::utils::{normalize_type_name, serialize, serialize_object, type_name_of};
+use types::{
+    Event, EventKind, Expiry, JournalEntry, KeyMeta, KeyRule, KeySchema, Metadata, Op, Snapshot,
+    Subscriber, TypeIndex, Watcher,
+};
 
 /// Create a memory-database
 ///
 pub fn mem<KV: KvInterface>() -> KvDb<KV> {
-    KvDb {
-        file_name: String::new(),
-        records: KV::default(),
-    }
+    KvDb::default()
 }
 
 /// Open/create a database file
@@ -37,19 +59,10 @@ pub fn create<KV: KvInterface<Key = BvString, Value = BvObject, RefKey = [u8]>>(
         .create(true)
         .open(file_name)?;
 
-    let mut reader = fio::reader::Reader::new(BufReader::new(f));
+    let mut db = read_from(f)?;
+    db.file_name = file_name.to_string();
 
-    if reader.is_empty() {
-        return Ok(KvDb {
-            file_name: file_name.to_string(),
-            records: KV::default(),
-        });
-    }
-
-    Ok(KvDb::<KV> {
-        file_name: file_name.to_string(),
-        records: reader.read_kv_records()?,
-    })
+    Ok(db)
 }
 
 /// Read from data type implementing io::Seek + io::Read
@@ -62,22 +75,117 @@ where
     let mut reader = fio::reader::Reader::new(BufReader::new(read));
 
     if reader.is_empty() {
-        return Ok(KvDb {
-            file_name: String::new(),
-            records: KV::default(),
-        });
+        return Ok(KvDb::default());
     }
 
-    Ok(KvDb::<KV> {
-        file_name: String::new(),
-        records: reader.read_kv_records()?,
-    })
+    let (records, metadata) = reader.read_kv_db()?;
+
+    Ok(KvDb::from_parts(records, metadata))
 }
 
 #[derive(Default)]
 pub struct KvDb<KV: KvInterface> {
     pub file_name: String,
     pub records: KV,
+    savepoints: Savepoints<Snapshot>,
+    watchers: Vec<Watcher>,
+    journal: Option<Journal<JournalEntry>>,
+    expiry: Expiry,
+    rules: BTreeMap<String, KeyRule>,
+    schemas: Vec<KeySchema>,
+    types: TypeIndex,
+    indexes: BTreeMap<String, index::SecondaryIndex>,
+    trees: BTreeMap<String, KvDb<KV>>,
+    cache: Option<cache::Cache>,
+    merge_operators: BTreeMap<String, merge::MergeFn>,
+    merge_bindings: Vec<(String, String)>,
+    subscribers: Vec<Subscriber>,
+    backlog: pubsub::Backlog,
+}
+
+impl<KV: KvInterface> KvDb<KV> {
+    /// Non-record data written to file on commit
+    ///
+    pub fn metadata(&self) -> Metadata {
+        let now = now_millis();
+        Metadata {
+            expires: self
+                .expiry
+                .iter()
+                .filter(|(_, at)| *at > now)
+                .map(|(key, at)| (key.clone(), at))
+                .collect(),
+            rules: self.rules.values().cloned().collect(),
+            schemas: self.schemas.clone(),
+            indexes: index::export(&self.indexes),
+            // Trees are serialized along with the records, see fio::encode_kv_db
+            trees: Vec::new(),
+        }
+    }
+
+    fn load_metadata(&mut self, metadata: Metadata) {
+        for (key, at) in metadata.expires {
+            self.expiry.set(key.as_slice(), at);
+        }
+
+        for rule in metadata.rules {
+            self.rules.insert(rule.base.clone(), rule);
+        }
+
+        self.schemas = metadata.schemas;
+        self.indexes = index::import(metadata.indexes);
+    }
+
+    /// Check if key has an expiry time which has passed
+    ///
+    pub fn is_expired(&self, key: &[u8]) -> bool {
+        !self.expiry.is_empty() && self.expiry.is_expired(key, now_millis())
+    }
+}
+
+impl<KV> KvDb<KV>
+where
+    KV: KvInterface<Key = BvString, Value = BvObject, RefKey = [u8]>,
+{
+    /// Build a database from records and metadata read from file, trees included
+    fn from_parts(records: KV, mut metadata: Metadata) -> Self {
+        let trees = std::mem::take(&mut metadata.trees);
+        let mut db = KvDb {
+            records,
+            ..KvDb::default()
+        };
+        db.load_metadata(metadata);
+        db.reindex();
+
+        for (name, section) in trees {
+            let (records, metadata) = parser::extract_db(&section);
+            db.trees.insert(name, KvDb::from_parts(records, metadata));
+        }
+
+        db
+    }
+
+    /// Rebuild indexes from the records
+    fn reindex(&mut self) {
+        self.types.clear();
+        for (key, value) in self.records.iter_after(None) {
+            self.types
+                .update(key.as_slice(), None, Some(value.type_name()));
+        }
+        self.rebuild_value_indexes();
+        self.rebuild_cache();
+    }
+}
+
+/// Maximum number of expired keys removed on each write
+const ACTIVE_SWEEP_LIMIT: usize = 20;
+
+/// Milliseconds since UNIX_EPOCH
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
 }
 
 impl<KV> KvDb<KV>
@@ -87,7 +195,9 @@ where
 {
     /// Write the in-memory database to file
     ///
+    /// Fails for memory-databases and trees, trees are written by their parent.
     pub fn commit(&self) -> std::io::Result<()> {
+        self.check_file_name()?;
         let f = std::fs::OpenOptions::new()
             .create(true)
             .write(true)
@@ -100,6 +210,17 @@ where
         Ok(())
     }
 
+    fn check_file_name(&self) -> std::io::Result<()> {
+        if self.file_name.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Database has no file, trees are committed through their parent",
+            ));
+        }
+
+        Ok(())
+    }
+
     /// Write the in-memory database to impl Write + Seek
     ///
     pub fn commit_to<W>(&self, writer: W) -> std::io::Result<()>
@@ -112,14 +233,102 @@ where
         Ok(())
     }
 
+    /// Replace all records with data, timeouts are removed
+    ///
     pub fn import(&mut self, data: Vec<(BvString, BvObject)>) {
         self.records.import(data);
+        self.expiry = Expiry::default();
+        self.reindex();
     }
 
     pub fn export(&self) -> Vec<(BvString, BvObject)> {
         self.records.export()
     }
 
+    /// Open a transaction, calling begin inside a transaction opens a nested one
+    ///
+    /// Transactions only live in memory, commit writes the current state whether
+    /// a transaction is open or not.
+    pub fn begin(&mut self) {
+        let snapshot = self.snapshot();
+        self.savepoints.begin(snapshot);
+    }
+
+    /// Close the innermost transaction and keep its changes
+    ///
+    pub fn end(&mut self) -> Result<(), String> {
+        self.savepoints.end().map(|_| ())
+    }
+
+    /// Close the innermost transaction and discard its changes
+    ///
+    pub fn rollback(&mut self) -> Result<(), String> {
+        let snapshot = self.savepoints.end()?;
+        self.restore(snapshot);
+        Ok(())
+    }
+
+    /// Create a named savepoint
+    ///
+    pub fn savepoint<S: AsRef<str>>(&mut self, name: S) {
+        let snapshot = self.snapshot();
+        self.savepoints.savepoint(name, snapshot);
+    }
+
+    /// Discard all changes made after savepoint `name`, the savepoint is kept
+    ///
+    /// Err if a transaction was begun after the savepoint and is still open.
+    pub fn rollback_to<S: AsRef<str>>(&mut self, name: S) -> Result<(), String> {
+        let snapshot = self.savepoints.rollback_to(name)?.clone();
+        self.restore(snapshot);
+        Ok(())
+    }
+
+    /// Remove savepoint `name` and all savepoints created after it, keeping their changes
+    ///
+    /// Err if a transaction was begun after the savepoint and is still open.
+    pub fn release<S: AsRef<str>>(&mut self, name: S) -> Result<(), String> {
+        self.savepoints.release(name)
+    }
+
+    /// Check if a transaction or savepoint is active
+    ///
+    pub fn in_transaction(&self) -> bool {
+        self.savepoints.is_active()
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            records: self.records.export(),
+            expiry: self.expiry.clone(),
+            indexes: index::export(&self.indexes),
+            trees: self
+                .trees
+                .iter()
+                .map(|(name, tree)| (name.clone(), tree.snapshot()))
+                .collect(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.records.import(snapshot.records);
+        self.expiry = snapshot.expiry;
+        self.indexes = index::import(snapshot.indexes);
+        self.reindex();
+
+        // Trees created after the snapshot are dropped, dropped trees are recreated
+        let mut trees = std::mem::take(&mut self.trees);
+        for (name, tree_snapshot) in snapshot.trees {
+            let mut tree = trees.remove(&name).unwrap_or_default();
+            tree.restore(tree_snapshot);
+            self.trees.insert(name, tree);
+        }
+
+        if let Some(journal) = self.journal.as_mut() {
+            journal.clear();
+        }
+    }
+
     /// Search keys for regex match
     #[cfg(feature = "regex_search")]
     pub fn key_regex<S: AsRef<str>>(&self, regex: S) -> Vec<(&BvString, &BvObject)> {
@@ -148,149 +357,275 @@ where
         self.filter(|(_, v)| v.is_str() && set.is_match(v.as_slice()))
     }
 
+    /// Iterate over records in key order in pages of at most `count` records
+    ///
+    /// Start with an empty cursor and pass the returned cursor to get the next page, an empty
+    /// cursor is returned after the last page. `match_pattern` is a glob style key pattern,
+    /// `type_filter` an exact type name, e.g "str".
+    ///
+    /// The cursor is the last returned key, so records existing during the whole scan are
+    /// returned exactly once, even if other keys are inserted or deleted between pages.
+    pub fn scan(
+        &self,
+        cursor: &str,
+        count: usize,
+        match_pattern: Option<&str>,
+        type_filter: Option<&str>,
+    ) -> (String, Vec<(&BvString, &BvObject)>) {
+        let count = count.max(1);
+        let now = now_millis();
+        let after = if cursor.is_empty() {
+            None
+        } else {
+            Some(cursor.as_bytes())
+        };
+
+        let mut page = self
+            .records
+            .iter_after(after)
+            .filter(|(k, _)| self.expiry.is_empty() || !self.expiry.is_expired(k.as_slice(), now))
+            .filter(|(k, v)| {
+                let key_match = match match_pattern {
+                    Some(pattern) => glob_match(k.as_slice(), pattern.as_bytes()),
+                    None => true,
+                };
+
+                let type_match = match type_filter {
+                    Some(type_name) => v.type_name() == type_name,
+                    None => true,
+                };
+
+                key_match && type_match
+            })
+            .take(count + 1)
+            .collect::<Vec<_>>();
+
+        let cursor = if page.len() > count {
+            page.truncate(count);
+            page.last().map(|(k, _)| k.to_string()).unwrap_or_default()
+        } else {
+            String::new()
+        };
+
+        (cursor, page)
+    }
+
+    /// Keys holding values of type T, sorted
+    ///
+    pub fn keys_of_type<T: ?Sized>(&self) -> Vec<&BvString> {
+        self.keys_of_type_name(String::from_utf8_lossy(type_name_of::<T>()))
+    }
+
+    /// Keys holding values with type name S, e.g "str", "list" or "hash", sorted
+    ///
+    pub fn keys_of_type_name<S: AsRef<str>>(&self, type_name: S) -> Vec<&BvString> {
+        self.types
+            .keys(type_name.as_ref().as_bytes())
+            .filter(|k| !self.is_expired(k.as_slice()))
+            .collect()
+    }
+
+    /// Keys and deserialized values of type T, sorted by key
+    ///
+    pub fn values_of_type<T: serde::de::DeserializeOwned>(&self) -> Vec<(&BvString, T)> {
+        self.keys_of_type::<T>()
+            .into_iter()
+            .map(|k| (k, self.records.get(k.as_slice()).unwrap().extract()))
+            .collect()
+    }
+
+    /// Number of keys of each type name
+    ///
+    pub fn count_by_type(&self) -> BTreeMap<String, usize> {
+        self.types
+            .iter()
+            .map(|(type_name, keys)| {
+                let count = keys
+                    .iter()
+                    .filter(|k| !self.is_expired(k.as_slice()))
+                    .count();
+                (type_name.to_string(), count)
+            })
+            .filter(|(_, count)| *count > 0)
+            .collect()
+    }
+
     /// Return the number of records stored in the database
     ///
     pub fn len(&self) -> usize {
-        self.records.len()
+        if self.expiry.is_empty() {
+            return self.records.len();
+        }
+
+        let expired = self
+            .expiry
+            .expired(now_millis())
+            .filter(|k| self.records.has_key(k.as_slice()))
+            .count();
+        self.records.len() - expired
     }
 
     /// Check if the key already exists
     ///
     pub fn has_key<S: AsRef<str>>(&self, key: S) -> bool {
-        self.records.has_key(key.as_ref().as_bytes())
-    }
-
-    /// Increment key by 1, isize is used by default if the key don't exists
-    ///
-    pub fn incr<S: AsRef<str>>(&mut self, key: S) {
-        if self.has_key(key.as_ref()) {
-            let v = self.get(key.as_ref()).unwrap().clone();
-            if v.is_int() || v.is_uint() || v.is_float() {
-                match v.type_name().as_str() {
-                    "i8" => self.set(key, v.extract::<i8>() + 1),
-                    "i16" => self.set(key, v.extract::<i16>() + 1),
-                    "i32" => self.set(key, v.extract::<i32>() + 1),
-                    "i64" => self.set(key, v.extract::<i64>() + 1),
-                    "i128" => self.set(key, v.extract::<i128>() + 1),
-                    "u8" => self.set(key, v.extract::<u8>() + 1),
-                    "u16" => self.set(key, v.extract::<u16>() + 1),
-                    "u32" => self.set(key, v.extract::<u32>() + 1),
-                    "u64" => self.set(key, v.extract::<u64>() + 1),
-                    "u128" => self.set(key, v.extract::<u128>() + 1),
-                    "f32" => self.set(key, v.extract::<f32>() + 1.0),
-                    "f64" => self.set(key, v.extract::<f64>() + 1.0),
-                    _ => panic!("Something went wrong"),
-                }
-            }
-        } else {
-            self.set(key, 1 as isize);
+        self.records.has_key(key.as_ref().as_bytes()) && !self.is_expired(key.as_ref().as_bytes())
+    }
+
+    /// Replace value with mem::replace and return the old value
+    ///
+    /// Err if the key don't exists, the value differs in type or length or violates a key schema.
+    pub fn swap<S: AsRef<str>, T: serde::Serialize>(
+        &mut self,
+        key: S,
+        value: T,
+    ) -> Result<BvObject, String> {
+        let new_obj = serialize_object(&value);
+        self.purge_expired(key.as_ref().as_bytes());
+        let old_obj = match self.records.get(key.as_ref().as_bytes()) {
+            Some(old_obj) => old_obj,
+            None => return Err(format!("Key \"{}\" does not exist", key.as_ref())),
+        };
+
+        if new_obj.type_name() != old_obj.type_name() || new_obj.raw().len() != old_obj.raw().len()
+        {
+            return Err("Not same type or equal length".to_string());
         }
+
+        let old_obj = self
+            .apply_checked(Op::Swap, key.as_ref().as_bytes(), Some(new_obj))?
+            .unwrap();
+        index::update_fields(&mut self.indexes, key.as_ref().as_bytes(), &value);
+        Ok(old_obj)
     }
 
-    /// Increment key by T, isize is used by default if the key don't exists
+    /// Set a key to value T
     ///
-    pub fn incr_by<S, T>(&mut self, key: S, val: T)
-    where
-        S: AsRef<str>,
-        T: serde::ser::Serialize + serde::de::DeserializeOwned + std::ops::Add,
-        <T as std::ops::Add>::Output: serde::ser::Serialize,
-    {
-        if self.has_key(key.as_ref()) {
-            let v = self.get(key.as_ref()).unwrap().clone();
-            if v.is_int() || v.is_uint() || v.is_float() {
-                self.set(key, v.extract::<T>() + val);
-            }
-        } else {
-            self.set(key, val);
-        }
-    }
-
-    /// Decrement key by 1, isize is used by default if the key don't exists
-    ///
-    pub fn decr<S: AsRef<str>>(&mut self, key: S) {
-        if self.has_key(key.as_ref()) {
-            let v = self.get(key.as_ref()).unwrap().clone();
-            if v.is_int() || v.is_uint() || v.is_float() {
-                match v.type_name().as_str() {
-                    "i8" => self.set(key, v.extract::<i8>() - 1),
-                    "i16" => self.set(key, v.extract::<i16>() - 1),
-                    "i32" => self.set(key, v.extract::<i32>() - 1),
-                    "i64" => self.set(key, v.extract::<i64>() - 1),
-                    "i128" => self.set(key, v.extract::<i128>() - 1),
-                    "u8" => self.set(key, v.extract::<u8>() - 1),
-                    "u16" => self.set(key, v.extract::<u16>() - 1),
-                    "u32" => self.set(key, v.extract::<u32>() - 1),
-                    "u64" => self.set(key, v.extract::<u64>() - 1),
-                    "u128" => self.set(key, v.extract::<u128>() - 1),
-                    "f32" => self.set(key, v.extract::<f32>() - 1.0),
-                    "f64" => self.set(key, v.extract::<f64>() - 1.0),
-                    _ => panic!("Something went wrong"),
-                }
-            }
-        } else {
-            self.set(key, 1 as isize);
+    /// Err if the value violates a key schema, see [key_schema](#method.key_schema), or
+    /// exceeds the byte budget of cache mode.
+    pub fn set<S: AsRef<str>, T: Sized + serde::ser::Serialize>(
+        &mut self,
+        key: S,
+        value: T,
+    ) -> Result<(), String> {
+        self.set_object(key.as_ref(), serialize_object(&value))?;
+        index::update_fields(&mut self.indexes, key.as_ref().as_bytes(), &value);
+        Ok(())
+    }
+
+    /// Set a key to value T which expires after ttl
+    ///
+    pub fn set_ex<S: AsRef<str>, T: Sized + serde::ser::Serialize>(
+        &mut self,
+        key: S,
+        value: T,
+        ttl: std::time::Duration,
+    ) -> Result<(), String> {
+        self.set_object(key.as_ref(), serialize_object(&value))?;
+        index::update_fields(&mut self.indexes, key.as_ref().as_bytes(), &value);
+        self.expiry.set(
+            key.as_ref().as_bytes(),
+            now_millis() + ttl.as_millis() as u64,
+        );
+        Ok(())
+    }
+
+    /// Set a timeout on key, returns false if the key don't exists
+    ///
+    pub fn expire<S: AsRef<str>>(&mut self, key: S, ttl: std::time::Duration) -> bool {
+        if !self.has_key(key.as_ref()) {
+            return false;
         }
+
+        self.expiry.set(
+            key.as_ref().as_bytes(),
+            now_millis() + ttl.as_millis() as u64,
+        );
+        true
     }
 
-    /// Decrement key by T, isize is used by default if the key don't exists
+    /// Remove the timeout of key, returns false if the key don't exists or has no timeout
     ///
-    pub fn decr_by<S, T>(&mut self, key: S, val: T)
-    where
-        S: AsRef<str>,
-        T: serde::ser::Serialize + serde::de::DeserializeOwned + std::ops::Sub,
-        <T as std::ops::Sub>::Output: serde::ser::Serialize,
-    {
-        if self.has_key(key.as_ref()) {
-            let v = self.get(key.as_ref()).unwrap().clone();
-            if v.is_int() || v.is_uint() || v.is_float() {
-                self.set(key, v.extract::<T>() - val);
-            }
-        } else {
-            self.set(key, val);
+    pub fn persist<S: AsRef<str>>(&mut self, key: S) -> bool {
+        if !self.has_key(key.as_ref()) {
+            return false;
         }
+
+        self.expiry.remove(key.as_ref().as_bytes()).is_some()
     }
 
-    /// Replace value with mem::replace and return the old value
+    /// Remaining time to live of key, None if the key don't exists or has no timeout
     ///
-    pub fn swap<S: AsRef<str>, T: serde::Serialize>(&mut self, key: S, value: T) -> BvObject {
-        let new_obj = serialize_object(&value);
-        let old_obj = self.records.get_mut(key.as_ref().as_bytes()).unwrap();
+    pub fn ttl<S: AsRef<str>>(&self, key: S) -> Option<std::time::Duration> {
+        let at = self.expiry.get(key.as_ref().as_bytes())?;
+        let now = now_millis();
 
-        if new_obj.type_name() == old_obj.type_name() && new_obj.raw().len() == old_obj.raw().len()
-        {
-            return std::mem::replace(old_obj, new_obj);
+        if at <= now {
+            return None;
         }
 
-        panic!("Not same type or equal length")
+        Some(std::time::Duration::from_millis(at - now))
     }
 
-    /// Set a key to value T
+    /// Remove all expired keys and return the number of removed keys
     ///
-    pub fn set<S: AsRef<str>, T: Sized + serde::ser::Serialize>(&mut self, key: S, value: T) {
-        if !self.has_key(key.as_ref()) {
-            // Create new
-            let value = serialize_object(&value);
-            assert!(!key.as_ref().is_empty() && !value.type_name().is_empty());
+    /// Expired keys are invisible to reads either way, a few are also removed
+    /// on every write. Watchers receive an EventKind::Expire event for each key.
+    pub fn sweep_expired(&mut self) -> usize {
+        self.sweep(usize::MAX)
+    }
 
-            self.records
-                .insert(key.as_ref().as_bytes().to_vec().into(), value);
-        } else {
-            // Update
-            let old = self
-                .records
-                .get(key.as_ref().as_bytes())
-                .unwrap()
-                .type_name();
-            let new = serialize_object(&value);
-
-            if old.inner().len() == new.inner().len() {
-                *self.records.get_mut(key.as_ref().as_bytes()).unwrap() = new;
-            } else {
-                self.del(key.as_ref());
-                self.records
-                    .insert(key.as_ref().as_bytes().to_vec().into(), new);
-            }
+    fn sweep(&mut self, limit: usize) -> usize {
+        if self.expiry.is_empty() {
+            return 0;
+        }
+
+        let expired = self
+            .expiry
+            .expired(now_millis())
+            .take(limit)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for key in expired.iter() {
+            self.purge_expired(key.as_slice());
+        }
+
+        expired.len()
+    }
+
+    /// Remove key if it has expired
+    fn purge_expired(&mut self, key: &[u8]) {
+        if self.is_expired(key) {
+            self.discard(key, EventKind::Expire);
+        }
+    }
+
+    /// Remove a record without journaling it, watchers are notified with kind
+    ///
+    /// Returns false if there was no record.
+    fn discard(&mut self, key: &[u8], kind: EventKind) -> bool {
+        self.expiry.remove(key);
+        let old = match self.records.remove(key) {
+            Some(old) => old,
+            None => return false,
+        };
+        self.types.update(key, Some(old.type_name()), None);
+        index::update(&mut self.indexes, key, None);
+        if let Some(cache) = self.cache.as_mut() {
+            cache.update(key, None);
         }
+
+        let event = Event {
+            kind,
+            key: key.into(),
+            old: Some(old),
+            new: None,
+        };
+
+        self.watchers
+            .retain(|w| !w.matches(key) || w.send(event.clone()));
+
+        true
     }
 
     /// Set a key to value T with type name S
@@ -300,84 +635,452 @@ where
         key: S,
         t: S,
         value: T,
-    ) {
+    ) -> Result<(), String> {
         let value = BvObject::from_raw(
             normalize_type_name(t.as_ref().as_bytes()).to_vec(),
             serialize(&value),
         );
-        assert!(!key.as_ref().is_empty() && !value.type_name().is_empty());
-        self.set(key, value);
+        self.set_object(key.as_ref(), value)
     }
 
     /// Set a key to Vec<u8> with type name S
     ///
-    pub fn set_raw<S: AsRef<str>>(&mut self, key: S, type_name: S, value: Vec<u8>) {
+    pub fn set_raw<S: AsRef<str>>(
+        &mut self,
+        key: S,
+        type_name: S,
+        value: Vec<u8>,
+    ) -> Result<(), String> {
         let value = BvObject::from_raw(
             normalize_type_name(type_name.as_ref().as_bytes()).to_vec(),
             value,
         );
-        assert!(!key.as_ref().is_empty() && !value.type_name().is_empty());
-        //self.set(key, value);
-        self.records
-            .insert(key.as_ref().as_bytes().to_vec().into(), value);
+        self.set_object(key.as_ref(), value)
     }
 
+    /// Set many keys, stops at the first value violating a key schema
+    ///
     pub fn set_many<S: AsRef<str>, T: Sized + serde::ser::Serialize>(
         &mut self,
         mut values: Vec<(S, T)>,
-    ) {
+    ) -> Result<(), String> {
         for (k, v) in values.drain(..) {
-            //let v = serialize_object(&v);
-            //assert!(k.as_ref().len() > 0 && v.type_name().len() > 0);
-            self.set(k, v);
+            self.set(k, v)?;
         }
+
+        Ok(())
     }
 
+    /// Set many keys with type names, stops at the first value violating a key schema
+    ///
     pub fn set_many_as<S: AsRef<str>, T: Sized + serde::ser::Serialize>(
         &mut self,
         values: Vec<(S, S, T)>,
-    ) {
+    ) -> Result<(), String> {
         for (k, t, v) in values {
-            let v = BvObject::from_raw(
-                normalize_type_name(t.as_ref().as_bytes()).to_vec(),
-                serialize(&v),
-            );
-            assert!(!k.as_ref().is_empty() && !v.type_name().is_empty());
-            self.set(k, v);
+            self.set_as(k, t, v)?;
         }
+
+        Ok(())
+    }
+
+    fn set_object(&mut self, key: &str, value: BvObject) -> Result<(), String> {
+        assert!(!key.is_empty() && !value.type_name().is_empty());
+        self.apply_checked(Op::Set, key.as_bytes(), Some(value))?;
+        self.expiry.remove(key.as_bytes());
+        Ok(())
     }
 
     /// Retrieve a BvObject
     ///
     pub fn get<S: AsRef<str>>(&self, key: S) -> Option<&BvObject> {
+        if self.is_expired(key.as_ref().as_bytes()) {
+            return None;
+        }
+
+        self.touch(key.as_ref().as_bytes());
+
         self.records.get(key.as_ref().as_bytes())
     }
 
     /// Retrieve and deserialize a value to T
     ///
     pub fn get_value<T: serde::de::DeserializeOwned>(&self, key: &str) -> T {
-        self.records.get(key.as_bytes()).unwrap().extract()
+        self.get(key).unwrap().extract()
     }
 
-    /// Retrieve a value as BvTuple
+    /// Retrieve a value as read-only BvTuple, elements are changed through update_tuple
     ///
-    pub fn get_tuple<S: AsRef<str>>(&mut self, key: S) -> Option<BvTuple> {
-        match self.records.get_mut(key.as_ref().as_bytes()) {
-            Some(t) => Some(BvTuple::from(t)),
-            None => None,
+    /// Err if the key don't exists or the value isn't a tuple, see BvTuple::from.
+    pub fn get_tuple<S: AsRef<str>>(&self, key: S) -> Result<BvTuple, String> {
+        match self.get(key.as_ref()) {
+            Some(t) => BvTuple::read_only(t),
+            None => Err(format!("Key \"{}\" does not exist", key.as_ref())),
         }
     }
 
+    /// Change the elements of a tuple and return the result of f
+    ///
+    /// The record is left unchanged if f returns Err or the changed tuple is rejected by
+    /// a key schema or the cache budget.
+    pub fn update_tuple<S, R, F>(&mut self, key: S, f: F) -> Result<R, String>
+    where
+        S: AsRef<str>,
+        F: FnOnce(&mut BvTuple) -> Result<R, String>,
+    {
+        let key_bytes = key.as_ref().as_bytes();
+        self.purge_expired(key_bytes);
+        let mut value = match self.records.get(key_bytes) {
+            Some(value) => value.clone(),
+            None => return Err(format!("Key \"{}\" does not exist", key.as_ref())),
+        };
+
+        let r = f(&mut BvTuple::from(&mut value)?)?;
+        self.check(key_bytes, &value)?;
+        self.modify(Op::UpdateTuple, key_bytes, |v| *v = value);
+
+        Ok(r)
+    }
+
     /// Retrieve a value as BvStr
     ///
     pub fn get_str<S: AsRef<str>>(&mut self, key: S) -> BvStr {
+        self.purge_expired(key.as_ref().as_bytes());
+        self.touch(key.as_ref().as_bytes());
+
         BvStr::from_bvobject(self.records.get_mut(key.as_ref().as_bytes()).unwrap())
     }
 
     /// Delete key and return the deleted object
     ///
     pub fn del<S: AsRef<str>>(&mut self, key: S) -> Option<BvObject> {
-        self.records.remove(key.as_ref().as_bytes())
+        self.apply(Op::Del, key.as_ref().as_bytes(), None)
+    }
+
+    /// Watch a single key for changes
+    ///
+    /// Events are sent after the mutation has been applied. Values edited in place
+    /// through get_str are not reported.
+    pub fn watch<S: AsRef<str>>(&mut self, key: S) -> std::sync::mpsc::Receiver<Event> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.watchers
+            .push(Watcher::new(key.as_ref().as_bytes(), true, sender));
+        receiver
+    }
+
+    /// Watch all keys starting with prefix for changes
+    ///
+    /// See [watch](#method.watch)
+    pub fn watch_prefix<S: AsRef<str>>(&mut self, prefix: S) -> std::sync::mpsc::Receiver<Event> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.watchers
+            .push(Watcher::new(prefix.as_ref().as_bytes(), false, sender));
+        receiver
+    }
+
+    /// Keep an undo/redo journal of at most `depth` operations
+    ///
+    /// set, del, swap, incr and decr are journaled, values edited in place through
+    /// get_str are not. Rolling back a transaction clears the journal.
+    pub fn enable_journal(&mut self, depth: usize) {
+        self.journal = Some(Journal::new(depth));
+    }
+
+    pub fn disable_journal(&mut self) {
+        self.journal = None;
+    }
+
+    /// Revert the latest journaled operation and return it
+    ///
+    /// The expiry and field index entries of the key are restored as well. Err if the
+    /// old value violates a key schema or the cache budget, the entry is kept.
+    pub fn undo(&mut self) -> Result<Option<JournalEntry>, String> {
+        // Taken out so the revert itself isn't journaled
+        let mut journal = match self.journal.take() {
+            Some(journal) => journal,
+            None => return Ok(None),
+        };
+
+        let r = match journal.undo() {
+            Some(mut entry) => {
+                entry.new_meta = self.key_meta(entry.key.as_slice());
+                match self.replay(&entry, entry.old.clone(), &entry.old_meta) {
+                    Ok(()) => {
+                        journal.undone(entry.clone());
+                        Ok(Some(entry))
+                    }
+                    Err(e) => {
+                        // Back on the undo history
+                        journal.redone(entry);
+                        Err(e)
+                    }
+                }
+            }
+            None => Ok(None),
+        };
+
+        self.journal = Some(journal);
+        r
+    }
+
+    /// Reapply the latest undone operation and return it
+    ///
+    /// Err if the new value violates a key schema or the cache budget, the entry is kept.
+    pub fn redo(&mut self) -> Result<Option<JournalEntry>, String> {
+        let mut journal = match self.journal.take() {
+            Some(journal) => journal,
+            None => return Ok(None),
+        };
+
+        let r = match journal.redo() {
+            Some(entry) => match self.replay(&entry, entry.new.clone(), &entry.new_meta) {
+                Ok(()) => {
+                    journal.redone(entry.clone());
+                    Ok(Some(entry))
+                }
+                Err(e) => {
+                    journal.undone(entry);
+                    Err(e)
+                }
+            },
+            None => Ok(None),
+        };
+
+        self.journal = Some(journal);
+        r
+    }
+
+    /// Write value and meta of a journaled key back
+    fn replay(
+        &mut self,
+        entry: &JournalEntry,
+        value: Option<BvObject>,
+        meta: &KeyMeta,
+    ) -> Result<(), String> {
+        let key = entry.key.as_slice();
+        self.apply_checked(entry.op, key, value)?;
+        match meta.expires_at {
+            Some(at) => self.expiry.set(key, at),
+            None => {
+                self.expiry.remove(key);
+            }
+        }
+        index::insert_fields(&mut self.indexes, key, &meta.fields);
+
+        Ok(())
+    }
+
+    fn key_meta(&self, key: &[u8]) -> KeyMeta {
+        KeyMeta {
+            expires_at: self.expiry.get(key),
+            fields: index::field_keys(&self.indexes, key),
+        }
+    }
+
+    /// Undoable operations, oldest first
+    ///
+    pub fn history(&self) -> Vec<&JournalEntry> {
+        match &self.journal {
+            Some(journal) => journal.history().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Insert, replace or remove(value None) a record, journal it and notify watchers
+    ///
+    /// Every mutation of a record goes through here.
+    fn apply(&mut self, op: Op, key: &[u8], value: Option<BvObject>) -> Option<BvObject> {
+        self.purge_expired(key);
+        self.sweep(ACTIVE_SWEEP_LIMIT);
+
+        let watched = self.watchers.iter().any(|w| w.matches(key));
+        let meta = self.journal.as_ref().map(|_| self.key_meta(key));
+        let new = if watched || self.journal.is_some() {
+            value.clone()
+        } else {
+            None
+        };
+
+        let new_type = value.as_ref().map(|v| v.type_name().clone());
+        let old = match value {
+            Some(value) => match self.records.get_mut(key) {
+                Some(old) => Some(std::mem::replace(old, value)),
+                None => {
+                    self.records.insert(key.to_vec().into(), value);
+                    None
+                }
+            },
+            None => {
+                self.expiry.remove(key);
+                self.records.remove(key)
+            }
+        };
+        self.types
+            .update(key, old.as_ref().map(|o| o.type_name()), new_type.as_ref());
+        index::update(&mut self.indexes, key, self.records.get(key));
+        if let Some(cache) = self.cache.as_mut() {
+            cache.update(key, self.records.get(key));
+        }
+
+        self.changed(op, key, old.clone(), new, meta, watched);
+        self.evict(key);
+
+        old
+    }
+
+    /// Retrieve a BvObject of type `type_name`, Err if the key holds another type
+    ///
+    fn get_typed(&self, key: &[u8], type_name: &str) -> Result<Option<&BvObject>, String> {
+        if self.is_expired(key) {
+            return Ok(None);
+        }
+        self.touch(key);
+
+        match self.records.get(key) {
+            Some(v) if v.type_name() != type_name => Err(format!(
+                "Expected type \"{}\" found type \"{}\" for key \"{}\"",
+                type_name,
+                v.type_name(),
+                String::from_utf8_lossy(key)
+            )),
+            v => Ok(v),
+        }
+    }
+
+    /// Edit a record in place, journal it and notify watchers
+    ///
+    /// Returns None if the key don't exists.
+    fn modify<R, F>(&mut self, op: Op, key: &[u8], f: F) -> Option<R>
+    where
+        F: FnOnce(&mut BvObject) -> R,
+    {
+        self.purge_expired(key);
+        self.sweep(ACTIVE_SWEEP_LIMIT);
+
+        let watched = self.watchers.iter().any(|w| w.matches(key));
+        let tracked = watched || self.journal.is_some();
+        let meta = self.journal.as_ref().map(|_| self.key_meta(key));
+
+        let value = self.records.get_mut(key)?;
+        let old = if tracked { Some(value.clone()) } else { None };
+        let r = f(value);
+        index::update(&mut self.indexes, key, Some(value));
+        if let Some(cache) = self.cache.as_mut() {
+            cache.update(key, Some(value));
+        }
+        let new = if tracked { Some(value.clone()) } else { None };
+
+        self.changed(op, key, old, new, meta, watched);
+        self.evict(key);
+
+        Some(r)
+    }
+
+    /// Apply value after validating it against key schemas and the cache budget
+    ///
+    /// Removals(value None) are never rejected.
+    fn apply_checked(
+        &mut self,
+        op: Op,
+        key: &[u8],
+        value: Option<BvObject>,
+    ) -> Result<Option<BvObject>, String> {
+        if let Some(value) = &value {
+            self.check(key, value)?;
+        }
+
+        Ok(self.apply(op, key, value))
+    }
+
+    /// Modify a record, the edited value is validated against key schemas and the cache
+    /// budget before it replaces the record
+    ///
+    /// The edit is made on a copy when a schema or byte budget applies to key.
+    fn modify_checked<R, F>(&mut self, op: Op, key: &[u8], f: F) -> Result<Option<R>, String>
+    where
+        F: FnOnce(&mut BvObject) -> R,
+    {
+        let max_bytes = self.cache_config().and_then(|c| c.max_bytes);
+        if max_bytes.is_none() && !self.has_schema(key) {
+            return Ok(self.modify(op, key, f));
+        }
+
+        self.purge_expired(key);
+        let mut value = match self.records.get(key) {
+            Some(value) => value.clone(),
+            None => return Ok(None),
+        };
+        let r = f(&mut value);
+        self.check(key, &value)?;
+        self.modify(op, key, |v| *v = value);
+
+        Ok(Some(r))
+    }
+
+    /// Validate value of key against key schemas and the cache budget
+    fn check(&self, key: &[u8], value: &BvObject) -> Result<(), String> {
+        self.validate(key, value)?;
+        self.check_cache_budget(key, value)
+    }
+
+    fn changed(
+        &mut self,
+        op: Op,
+        key: &[u8],
+        old: Option<BvObject>,
+        new: Option<BvObject>,
+        meta: Option<KeyMeta>,
+        watched: bool,
+    ) {
+        if let (Some(journal), true) = (self.journal.as_mut(), old.is_some() || new.is_some()) {
+            journal.record(JournalEntry {
+                op,
+                key: key.into(),
+                old: old.clone(),
+                new: new.clone(),
+                old_meta: meta.unwrap_or_default(),
+                new_meta: KeyMeta::default(),
+            });
+        }
+
+        if watched {
+            self.notify(key, old, new);
+        }
+    }
+
+    fn notify(&mut self, key: &[u8], old: Option<BvObject>, new: Option<BvObject>) {
+        let kind = match (&old, &new) {
+            (None, Some(_)) => EventKind::Insert,
+            (Some(_), Some(_)) => EventKind::Update,
+            (Some(_), None) => EventKind::Delete,
+            (None, None) => return,
+        };
+
+        let event = Event {
+            kind,
+            key: key.into(),
+            old,
+            new,
+        };
+
+        // Drop watchers whose receiver is gone
+        self.watchers
+            .retain(|w| !w.matches(key) || w.send(event.clone()));
+    }
+}
+
+impl<KV> KvDb<KV>
+where
+    KV: KvInterface,
+    for<'a> &'a KV: IntoIterator<Item = (&'a BvString, &'a BvObject)>,
+{
+    /// Iterate over all records, expired keys excluded
+    ///
+    pub fn iter(&self) -> impl Iterator<Item = (&BvString, &BvObject)> {
+        let now = now_millis();
+        (&self.records).into_iter().filter(move |(k, _)| {
+            self.expiry.is_empty() || !self.expiry.is_expired(k.as_slice(), now)
+        })
     }
 }
 
@@ -390,7 +1093,7 @@ where
     where
         F: Fn((&BvString, &BvObject)) -> bool,
     {
-        (&self.records).into_iter().filter(|t| cb(*t)).collect()
+        self.iter().filter(|t| cb(*t)).collect()
     }
 }
 
@@ -401,8 +1104,7 @@ where
 {
     fn starts_with<S: AsRef<str>>(&self, key_part: S) -> Vec<(&BvString, &BvObject)> {
         let k_part = key_part.as_ref().as_bytes();
-        (&self.records)
-            .into_iter()
+        self.iter()
             .filter_map(|(k, v)| {
                 if k.starts_with(k_part) {
                     return Some((k, v));
@@ -414,8 +1116,7 @@ where
     }
 
     fn ends_with<S: AsRef<str>>(&self, key_part: S) -> Vec<(&BvString, &BvObject)> {
-        (&self.records)
-            .into_iter()
+        self.iter()
             .filter_map(|(k, v)| {
                 if k.ends_with(key_part.as_ref().as_bytes()) {
                     return Some((k, v));
@@ -427,8 +1128,7 @@ where
     }
 
     fn contains<S: AsRef<str>>(&self, key_part: S) -> Vec<(&BvString, &BvObject)> {
-        (&self.records)
-            .into_iter()
+        self.iter()
             .filter_map(|(k, v)| {
                 if k.contains(key_part.as_ref()) {
                     return Some((k, v));
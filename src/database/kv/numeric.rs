@@ -0,0 +1,81 @@
+//! Arithmetic on numeric values of KvDb
+//!
+//! Stored values keep their type, see [numeric](../../../types/bv/numeric/index.html) for
+//! type promotion when the operand is of another type. Time to live of the key is kept.
+
+use super::types::Op;
+use super::KvDb;
+use crate::storage::KvInterface;
+use crate::types::bv::numeric::{self, NumOp, Overflow};
+use crate::types::bv::{BvObject, BvString};
+use crate::utils::serialize_object;
+
+impl<KV> KvDb<KV>
+where
+    KV: KvInterface<Key = BvString, Value = BvObject, RefKey = [u8]>,
+    for<'a> &'a KV: IntoIterator<Item = (&'a BvString, &'a BvObject)>,
+{
+    /// Increment key by 1 and return the new value, isize is used by default if the key don't exists
+    ///
+    /// Err if the value isn't numeric or overflows its type.
+    pub fn incr<S: AsRef<str>>(&mut self, key: S) -> Result<BvObject, String> {
+        self.compute(key, NumOp::Add, 1isize, Overflow::Checked)
+    }
+
+    /// Increment key by val and return the new value, val is stored if the key don't exists
+    ///
+    pub fn incr_by<S: AsRef<str>, T: serde::Serialize>(
+        &mut self,
+        key: S,
+        val: T,
+    ) -> Result<BvObject, String> {
+        self.compute(key, NumOp::Add, val, Overflow::Checked)
+    }
+
+    /// Decrement key by 1 and return the new value, isize is used by default if the key don't exists
+    ///
+    pub fn decr<S: AsRef<str>>(&mut self, key: S) -> Result<BvObject, String> {
+        self.compute(key, NumOp::Sub, 1isize, Overflow::Checked)
+    }
+
+    /// Decrement key by val and return the new value, a missing key is treated as zero of the type of val
+    ///
+    pub fn decr_by<S: AsRef<str>, T: serde::Serialize>(
+        &mut self,
+        key: S,
+        val: T,
+    ) -> Result<BvObject, String> {
+        self.compute(key, NumOp::Sub, val, Overflow::Checked)
+    }
+
+    /// Apply `value op operand` to key and return the new value
+    ///
+    /// A missing key is treated as zero of the type of operand, min and max store the operand.
+    pub fn compute<S: AsRef<str>, T: serde::Serialize>(
+        &mut self,
+        key: S,
+        op: NumOp,
+        operand: T,
+        overflow: Overflow,
+    ) -> Result<BvObject, String> {
+        let key = key.as_ref();
+        let operand = serialize_object(&operand);
+
+        let value = match self.get(key) {
+            Some(value) => numeric::calc(value, op, &operand, overflow)?,
+            None if op == NumOp::Min || op == NumOp::Max => {
+                numeric::calc(&operand, op, &operand, overflow)?
+            }
+            None => numeric::calc(&numeric::zero(&operand)?, op, &operand, overflow)?,
+        };
+
+        let op = match op {
+            NumOp::Add => Op::Incr,
+            NumOp::Sub => Op::Decr,
+            _ => Op::Compute,
+        };
+        self.apply_checked(op, key.as_bytes(), Some(value.clone()))?;
+
+        Ok(value)
+    }
+}
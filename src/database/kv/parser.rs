@@ -1,3 +1,4 @@
+use super::types::{IndexDef, KeyRule, KeySchema, Metadata};
 use crate::byte_size::globals::*;
 use crate::storage::KvInterface;
 use crate::types::cursor::Cursor;
@@ -51,25 +52,169 @@ pub fn extract_single(v: &[u8], k_len: usize, t_len: usize, v_len: usize) -> (Ve
     )
 }
 
+pub fn get_expire_len(v: &[u8]) -> usize {
+    assert_eq!(&v[..3], kv::EXPIRE_IDENT);
+    v[3] as usize
+}
+
+pub fn extract_expire(v: &[u8], k_len: usize) -> (BvString, u64) {
+    assert_eq!(&v[..3], kv::EXPIRE_IDENT);
+    let mut cursor = Cursor::new(v);
+    cursor.jump(kv::EXPIRE_IDENT.len() + K_LEN_BS);
+    let at = deserialize::<u64>(cursor.get(U64_BS));
+    (cursor.get(k_len).into(), at)
+}
+
+pub fn get_rule_len(v: &[u8]) -> usize {
+    assert_eq!(&v[..3], kv::RULE_IDENT);
+    deserialize::<u32>(&v[3..3 + V_LEN_BS]) as usize
+}
+
+pub fn extract_rule(v: &[u8]) -> KeyRule {
+    assert_eq!(&v[..3], kv::RULE_IDENT);
+    deserialize(&v[kv::RULE_HEAD_BS..])
+}
+
+pub fn get_schema_len(v: &[u8]) -> usize {
+    assert_eq!(&v[..3], kv::SCHEMA_IDENT);
+    deserialize::<u32>(&v[3..3 + V_LEN_BS]) as usize
+}
+
+pub fn extract_schema(v: &[u8]) -> KeySchema {
+    assert_eq!(&v[..3], kv::SCHEMA_IDENT);
+    deserialize(&v[kv::SCHEMA_HEAD_BS..])
+}
+
+pub fn get_index_len(v: &[u8]) -> usize {
+    assert_eq!(&v[..3], kv::INDEX_IDENT);
+    deserialize::<u32>(&v[3..3 + V_LEN_BS]) as usize
+}
+
+pub fn extract_index(v: &[u8]) -> (IndexDef, Vec<(BvString, Vec<u8>)>) {
+    assert_eq!(&v[..3], kv::INDEX_IDENT);
+    deserialize(&v[kv::INDEX_HEAD_BS..])
+}
+
+pub fn get_tree_len(v: &[u8]) -> (usize, usize) {
+    assert_eq!(&v[..3], kv::TREE_IDENT);
+    (
+        v[3] as usize,
+        deserialize::<u64>(&v[4..4 + U64_BS]) as usize,
+    )
+}
+
+pub fn extract_tree(v: &[u8], n_len: usize) -> (String, Vec<u8>) {
+    assert_eq!(&v[..3], kv::TREE_IDENT);
+    let mut cursor = Cursor::new(v);
+    cursor.jump(kv::TREE_HEAD_BS);
+    let name = String::from_utf8(cursor.get(n_len).to_vec()).unwrap();
+    (name, cursor.remaining().to_vec())
+}
+
+/// Byte size of the record or metadata item starting at v
+///
+pub fn item_len(v: &[u8]) -> usize {
+    let ident = &v[..kv::IDENT.len()];
+
+    if ident == kv::IDENT {
+        let (k_len, t_len, v_len) = get_ktv_len(v);
+        kv::IDENT_HEAD_BS + k_len + t_len + v_len
+    } else if ident == kv::EXPIRE_IDENT {
+        kv::EXPIRE_HEAD_BS + get_expire_len(v)
+    } else if ident == kv::RULE_IDENT {
+        kv::RULE_HEAD_BS + get_rule_len(v)
+    } else if ident == kv::SCHEMA_IDENT {
+        kv::SCHEMA_HEAD_BS + get_schema_len(v)
+    } else if ident == kv::INDEX_IDENT {
+        kv::INDEX_HEAD_BS + get_index_len(v)
+    } else if ident == kv::TREE_IDENT {
+        let (n_len, s_len) = get_tree_len(v);
+        kv::TREE_HEAD_BS + n_len + s_len
+    } else {
+        panic!("Unknown record identifier {:?}", ident);
+    }
+}
+
+/// Split the main keyspace from named trees without decoding records
+///
+pub fn split_trees(v: &[u8]) -> (Vec<u8>, Vec<(String, Vec<u8>)>) {
+    let mut main = Vec::new();
+    let mut trees = Vec::new();
+
+    let mut cursor = Cursor::new(v);
+    while cursor.remaining_len() >= kv::IDENT.len() {
+        let len = item_len(cursor.peek(cursor.remaining_len()));
+        let item = cursor.get(len);
+
+        if item[..3] == kv::TREE_IDENT {
+            let (n_len, _) = get_tree_len(item);
+            trees.push(extract_tree(item, n_len));
+        } else {
+            main.extend_from_slice(item);
+        }
+    }
+
+    (main, trees)
+}
+
 pub fn extract_records<KV: KvInterface<Key = BvString, Value = BvObject>>(v: &[u8]) -> KV {
-    assert_eq!(v[..3], kv::IDENT);
-    let idxs = seqs_find_all(&v, &kv::IDENT);
+    extract_db(v).0
+}
 
-    //let mut vec = std::collections::HashMap::with_capacity(idxs.len());
+/// Extract records and metadata, records are stored back to back, each starting with its identifier
+///
+pub fn extract_db<KV: KvInterface<Key = BvString, Value = BvObject>>(v: &[u8]) -> (KV, Metadata) {
     let mut storage = KV::default();
+    let mut metadata = Metadata::default();
 
-    let mut cursor = Cursor::new(&v);
-    for idx in idxs {
-        cursor.jump(idx);
-        let (k_len, t_len, v_len) = get_ktv_len(cursor.peek(kv::IDENT_HEAD_BS));
-        let (k, v) = extract_single(
-            cursor.get(kv::IDENT_HEAD_BS + k_len + t_len + v_len),
-            k_len,
-            t_len,
-            v_len,
-        );
-        storage.insert(BvString::from(k), v);
+    let mut cursor = Cursor::new(v);
+    while cursor.remaining_len() >= kv::IDENT.len() {
+        let ident = cursor.peek(kv::IDENT.len());
+
+        if ident == kv::IDENT {
+            let (k_len, t_len, v_len) = get_ktv_len(cursor.peek(kv::IDENT_HEAD_BS));
+            let (k, v) = extract_single(
+                cursor.get(kv::IDENT_HEAD_BS + k_len + t_len + v_len),
+                k_len,
+                t_len,
+                v_len,
+            );
+            storage.insert(BvString::from(k), v);
+        } else if ident == kv::EXPIRE_IDENT {
+            let k_len = get_expire_len(cursor.peek(kv::EXPIRE_HEAD_BS));
+            metadata.expires.push(extract_expire(
+                cursor.get(kv::EXPIRE_HEAD_BS + k_len),
+                k_len,
+            ));
+        } else if ident == kv::RULE_IDENT {
+            let len = get_rule_len(cursor.peek(kv::RULE_HEAD_BS));
+            metadata
+                .rules
+                .push(extract_rule(cursor.get(kv::RULE_HEAD_BS + len)));
+        } else if ident == kv::SCHEMA_IDENT {
+            let len = get_schema_len(cursor.peek(kv::SCHEMA_HEAD_BS));
+            metadata
+                .schemas
+                .push(extract_schema(cursor.get(kv::SCHEMA_HEAD_BS + len)));
+        } else if ident == kv::INDEX_IDENT {
+            let len = get_index_len(cursor.peek(kv::INDEX_HEAD_BS));
+            metadata
+                .indexes
+                .push(extract_index(cursor.get(kv::INDEX_HEAD_BS + len)));
+        } else if ident == kv::TREE_IDENT {
+            let (n_len, s_len) = get_tree_len(cursor.peek(kv::TREE_HEAD_BS));
+            metadata.trees.push(extract_tree(
+                cursor.get(kv::TREE_HEAD_BS + n_len + s_len),
+                n_len,
+            ));
+        } else {
+            panic!(
+                "Unknown record identifier {:?} at {}",
+                ident,
+                cursor.position()
+            );
+        }
     }
 
-    storage
+    (storage, metadata)
 }
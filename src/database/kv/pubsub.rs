@@ -0,0 +1,96 @@
+//! Publish/subscribe channels of KvDb
+//!
+//! Channels are independent of keys and live in memory only. Subscribers receive messages
+//! published after they subscribed, and the messages in the backlog if one is kept.
+
+use std::collections::VecDeque;
+use std::sync::mpsc::Receiver;
+
+use super::types::{Message, Subscriber};
+use super::KvDb;
+use crate::storage::KvInterface;
+use crate::utils::serialize_object;
+
+/// Most recent messages of all channels, oldest first
+#[derive(Default)]
+pub struct Backlog {
+    max_len: usize,
+    messages: VecDeque<Message>,
+}
+
+impl<KV: KvInterface> KvDb<KV> {
+    /// Send value to all subscribers of channel and return the number of receiving subscribers
+    ///
+    pub fn publish<S: AsRef<str>, T: serde::Serialize>(&mut self, channel: S, value: T) -> usize {
+        let message = Message {
+            channel: channel.as_ref().to_string(),
+            payload: serialize_object(&value),
+        };
+
+        let mut received = 0;
+        // Drop subscribers whose receiver is gone
+        self.subscribers.retain(|s| {
+            if !s.matches(&message.channel) {
+                return true;
+            }
+
+            let sent = s.send(message.clone());
+            received += sent as usize;
+            sent
+        });
+
+        if self.backlog.max_len > 0 {
+            if self.backlog.messages.len() == self.backlog.max_len {
+                self.backlog.messages.pop_front();
+            }
+            self.backlog.messages.push_back(message);
+        }
+
+        received
+    }
+
+    /// Subscribe to a channel, messages in the backlog are received first
+    ///
+    pub fn subscribe<S: AsRef<str>>(&mut self, channel: S) -> Receiver<Message> {
+        self.add_subscriber(channel.as_ref(), false)
+    }
+
+    /// Subscribe to all channels matching a glob style pattern, e.g "news.*"
+    ///
+    /// See [subscribe](#method.subscribe)
+    pub fn psubscribe<S: AsRef<str>>(&mut self, pattern: S) -> Receiver<Message> {
+        self.add_subscriber(pattern.as_ref(), true)
+    }
+
+    /// Number of subscribers receiving messages published to channel
+    ///
+    pub fn numsub<S: AsRef<str>>(&self, channel: S) -> usize {
+        self.subscribers
+            .iter()
+            .filter(|s| s.matches(channel.as_ref()))
+            .count()
+    }
+
+    /// Keep the last `max_len` published messages for late subscribers, 0 keeps none
+    ///
+    pub fn set_pubsub_backlog(&mut self, max_len: usize) {
+        self.backlog.max_len = max_len;
+        while self.backlog.messages.len() > max_len {
+            self.backlog.messages.pop_front();
+        }
+    }
+
+    fn add_subscriber(&mut self, channel: &str, pattern: bool) -> Receiver<Message> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let subscriber = Subscriber::new(channel, pattern, sender);
+
+        for message in self.backlog.messages.iter() {
+            if subscriber.matches(&message.channel) {
+                subscriber.send(message.clone());
+            }
+        }
+
+        self.subscribers.push(subscriber);
+        receiver
+    }
+}
@@ -0,0 +1,166 @@
+//! Rename and copy keys of KvDb
+//!
+//! Values keep their time to live and type, indexes are updated. Entries of field indexes
+//! are kept if the index also covers the new key. Each moved key is journaled as a removal
+//! of the old and a write of the new key.
+
+use std::collections::BTreeSet;
+
+use super::index;
+use super::types::Op;
+use super::KvDb;
+use crate::storage::KvInterface;
+use crate::types::bv::{BvObject, BvString};
+
+impl<KV> KvDb<KV>
+where
+    KV: KvInterface<Key = BvString, Value = BvObject, RefKey = [u8]>,
+    for<'a> &'a KV: IntoIterator<Item = (&'a BvString, &'a BvObject)>,
+{
+    /// Rename key, an existing new_key is overwritten
+    ///
+    /// Err if the key don't exists or the value violates a key schema of new_key.
+    pub fn rename<S: AsRef<str>>(&mut self, key: S, new_key: S) -> Result<(), String> {
+        let (key, new_key) = (key.as_ref().as_bytes(), new_key.as_ref().as_bytes());
+        self.expect_key(key)?;
+
+        if key == new_key {
+            return Ok(());
+        }
+
+        self.move_keys(Op::Rename, vec![(key.into(), new_key.into())], false)
+    }
+
+    /// Rename key if new_key don't exists, returns false if it exists
+    ///
+    pub fn rename_nx<S: AsRef<str>>(&mut self, key: S, new_key: S) -> Result<bool, String> {
+        self.expect_key(key.as_ref().as_bytes())?;
+
+        if self.exists(new_key.as_ref().as_bytes()) {
+            return Ok(false);
+        }
+
+        self.rename(key, new_key)?;
+        Ok(true)
+    }
+
+    /// Copy key to destination, returns false if destination exists and replace is false
+    ///
+    /// Err if the key don't exists, key and destination are the same or the value violates
+    /// a key schema of destination.
+    pub fn copy<S: AsRef<str>>(
+        &mut self,
+        key: S,
+        destination: S,
+        replace: bool,
+    ) -> Result<bool, String> {
+        let (key, destination) = (key.as_ref().as_bytes(), destination.as_ref().as_bytes());
+        self.expect_key(key)?;
+
+        if key == destination {
+            return Err("Source and destination are the same key".to_string());
+        }
+
+        if !replace && self.exists(destination) {
+            return Ok(false);
+        }
+
+        self.move_keys(Op::Copy, vec![(key.into(), destination.into())], true)?;
+        Ok(true)
+    }
+
+    /// Replace prefix with new_prefix in all keys starting with prefix, returns the number of renamed keys
+    ///
+    /// Either all or no keys are renamed. Err if a new key exists which isn't renamed itself
+    /// or a value violates a key schema of its new key.
+    pub fn rename_prefix<S: AsRef<str>>(
+        &mut self,
+        prefix: S,
+        new_prefix: S,
+    ) -> Result<usize, String> {
+        let (prefix, new_prefix) = (prefix.as_ref().as_bytes(), new_prefix.as_ref().as_bytes());
+
+        if prefix == new_prefix {
+            return Ok(0);
+        }
+
+        let moves = self
+            .records
+            .iter_after(None)
+            .skip_while(|(k, _)| !k.as_slice().starts_with(prefix))
+            .take_while(|(k, _)| k.as_slice().starts_with(prefix))
+            .filter(|(k, _)| !self.is_expired(k.as_slice()))
+            .map(|(k, _)| {
+                let mut new_key = new_prefix.to_vec();
+                new_key.extend_from_slice(&k.as_slice()[prefix.len()..]);
+                (k.clone(), BvString::from(new_key))
+            })
+            .collect::<Vec<_>>();
+
+        for (_, new_key) in moves.iter() {
+            if self.exists(new_key.as_slice()) && !new_key.as_slice().starts_with(prefix) {
+                return Err(format!("Key \"{}\" already exists", new_key));
+            }
+        }
+
+        let renamed = moves.len();
+        self.move_keys(Op::Rename, moves, false)?;
+        Ok(renamed)
+    }
+
+    fn exists(&self, key: &[u8]) -> bool {
+        self.records.has_key(key) && !self.is_expired(key)
+    }
+
+    fn expect_key(&self, key: &[u8]) -> Result<(), String> {
+        if self.exists(key) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Key \"{}\" does not exist",
+                String::from_utf8_lossy(key)
+            ))
+        }
+    }
+
+    /// Write the values of the old keys to the new keys, with their time to live and
+    /// field index entries. All new keys are validated before any key is written.
+    fn move_keys(
+        &mut self,
+        op: Op,
+        moves: Vec<(BvString, BvString)>,
+        keep_old: bool,
+    ) -> Result<(), String> {
+        let old_keys = if keep_old {
+            BTreeSet::new()
+        } else {
+            moves.iter().map(|(k, _)| k.clone()).collect()
+        };
+
+        let mut moved = Vec::with_capacity(moves.len());
+        for (key, new_key) in moves {
+            let value = self.records.get(key.as_slice()).unwrap().clone();
+            self.validate_except(new_key.as_slice(), &value, &old_keys)?;
+
+            let at = self.expiry.get(key.as_slice());
+            let field_keys = index::field_keys(&self.indexes, key.as_slice());
+            moved.push((new_key, value, at, field_keys));
+        }
+
+        for key in old_keys.iter() {
+            self.apply(op, key.as_slice(), None);
+        }
+
+        for (new_key, value, at, field_keys) in moved {
+            self.expiry.remove(new_key.as_slice());
+            self.apply(op, new_key.as_slice(), Some(value));
+            index::insert_fields(&mut self.indexes, new_key.as_slice(), &field_keys);
+
+            if let Some(at) = at {
+                self.expiry.set(new_key.as_slice(), at);
+            }
+        }
+
+        Ok(())
+    }
+}
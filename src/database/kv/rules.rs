@@ -0,0 +1,135 @@
+//! Key rules, generating keys for values stored with `KvDb::store`
+//!
+//! Rules and their counters are written to the database file on commit. Counters are
+//! not restored by transaction rollbacks, a rolled back key is never generated again.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use super::types::{KeyRule, KvRule};
+use super::{now_millis, KvDb};
+use crate::storage::KvInterface;
+use crate::types::bv::{BvObject, BvString};
+
+impl<KV> KvDb<KV>
+where
+    KV: KvInterface<Key = BvString, Value = BvObject, RefKey = [u8]>,
+    for<'a> &'a KV: IntoIterator<Item = (&'a BvString, &'a BvObject)>,
+{
+    /// Register a rule generating keys `<base><separator><suffix>` for `store(base, value)`
+    ///
+    /// Replaces any previous rule of base, the counter is kept if the rule is unchanged.
+    pub fn key_config<B: AsRef<str>, P: AsRef<str>>(
+        &mut self,
+        base: B,
+        separator: P,
+        rule: KvRule,
+    ) {
+        let base = base.as_ref().to_string();
+        let counter = match self.rules.get(&base) {
+            Some(prev) if prev.rule == rule => prev.counter,
+            _ => 0,
+        };
+
+        self.rules.insert(
+            base.clone(),
+            KeyRule {
+                base,
+                separator: separator.as_ref().to_string(),
+                rule,
+                counter,
+            },
+        );
+    }
+
+    /// Remove the key rule of base, returns false if base has no rule
+    ///
+    pub fn remove_key_config<B: AsRef<str>>(&mut self, base: B) -> bool {
+        self.rules.remove(base.as_ref()).is_some()
+    }
+
+    /// The key rule of base
+    ///
+    pub fn key_rule<B: AsRef<str>>(&self, base: B) -> Option<&KeyRule> {
+        self.rules.get(base.as_ref())
+    }
+
+    /// Store value under a key generated by the rule of base and return the key
+    ///
+    /// Generated keys never overwrite existing keys. The counter of the rule only advances
+    /// if the value is written.
+    pub fn store<B: AsRef<str>, T: serde::Serialize>(
+        &mut self,
+        base: B,
+        value: T,
+    ) -> Result<String, String> {
+        let (key, rule) = self.generate_key(base.as_ref())?;
+        self.set(&key, value)?;
+        self.rules.insert(rule.base.clone(), rule);
+
+        Ok(key)
+    }
+
+    /// Next key of base and the rule with its advanced counter
+    fn generate_key(&self, base: &str) -> Result<(String, KeyRule), String> {
+        let mut rule = self
+            .rules
+            .get(base)
+            .cloned()
+            .ok_or_else(|| format!("No key rule for \"{}\"", base))?;
+        let prefix = format!("{}{}", rule.base, rule.separator);
+
+        let key = loop {
+            let suffix = match rule.rule {
+                KvRule::AutoIncrement => {
+                    rule.counter += 1;
+                    (rule.counter - 1).to_string()
+                }
+                KvRule::Timestamp => {
+                    rule.counter = now_millis().max(rule.counter + 1);
+                    rule.counter.to_string()
+                }
+                KvRule::Uuid => uuid_v4(),
+            };
+
+            let key = format!("{}{}", prefix, suffix);
+            if !self.has_key(&key) {
+                break key;
+            }
+        };
+
+        Ok((key, rule))
+    }
+}
+
+/// UUID version 4, randomness from the randomly keyed std hasher mixed with the time
+///
+/// Good enough for unique keys, not for anything requiring unpredictable values.
+fn uuid_v4() -> String {
+    let random = || {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u128(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos(),
+        );
+        hasher.finish()
+    };
+
+    let mut b = [0u8; 16];
+    b[..8].copy_from_slice(&random().to_le_bytes());
+    b[8..].copy_from_slice(&random().to_le_bytes());
+    b[6] = (b[6] & 0x0f) | 0x40; // Version 4
+    b[8] = (b[8] & 0x3f) | 0x80; // RFC 4122 variant
+
+    let hex = b.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
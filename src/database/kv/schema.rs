@@ -0,0 +1,147 @@
+//! Key schemas, constraints on values of keys matching a pattern
+//!
+//! Schemas are written to the database file on commit and validated by every write, including
+//! the operations on lists, sets, sorted sets, hashes, streams, bitmaps and HyperLogLogs. Writes
+//! violating a schema return Err and leave the record unchanged. Existing records are not
+//! validated when a schema is registered.
+
+use std::collections::BTreeSet;
+
+use super::types::KeySchema;
+use super::KvDb;
+use crate::slice::glob_match;
+use crate::storage::KvInterface;
+use crate::types::bv::{elements, BvObject, BvString};
+
+impl<KV> KvDb<KV>
+where
+    KV: KvInterface<Key = BvString, Value = BvObject, RefKey = [u8]>,
+    for<'a> &'a KV: IntoIterator<Item = (&'a BvString, &'a BvObject)>,
+{
+    /// Register a schema, replacing any previous schema with the same pattern
+    ///
+    /// Patterns are glob style, `*` matches any sequence, `?` any single character and
+    /// `[0-9]` a set of characters. A key may match multiple schemas.
+    pub fn key_schema(&mut self, schema: KeySchema) {
+        match self
+            .schemas
+            .iter_mut()
+            .find(|s| s.pattern == schema.pattern)
+        {
+            Some(s) => *s = schema,
+            None => self.schemas.push(schema),
+        }
+    }
+
+    /// Remove the schema of pattern, returns false if no such schema exists
+    ///
+    pub fn remove_key_schema<P: AsRef<str>>(&mut self, pattern: P) -> bool {
+        let len = self.schemas.len();
+        self.schemas.retain(|s| s.pattern != pattern.as_ref());
+        self.schemas.len() != len
+    }
+
+    pub fn key_schemas(&self) -> &[KeySchema] {
+        &self.schemas
+    }
+
+    /// Check if any schema matches key
+    pub(super) fn has_schema(&self, key: &[u8]) -> bool {
+        self.schemas
+            .iter()
+            .any(|s| glob_match(key, s.pattern.as_bytes()))
+    }
+
+    /// Check value against the schemas matching key
+    pub(super) fn validate(&self, key: &[u8], value: &BvObject) -> Result<(), String> {
+        self.validate_except(key, value, &BTreeSet::new())
+    }
+
+    /// Validate value of key, values held by keys in `except` are ignored by unique schemas
+    pub(super) fn validate_except(
+        &self,
+        key: &[u8],
+        value: &BvObject,
+        except: &BTreeSet<BvString>,
+    ) -> Result<(), String> {
+        for schema in self
+            .schemas
+            .iter()
+            .filter(|s| glob_match(key, s.pattern.as_bytes()))
+        {
+            let violation = |reason: String| {
+                format!(
+                    "Key \"{}\" violates schema \"{}\": {}",
+                    String::from_utf8_lossy(key),
+                    schema.pattern,
+                    reason
+                )
+            };
+
+            if let Some(type_name) = &schema.type_name {
+                if value.type_name() != type_name.as_str() {
+                    return Err(violation(format!(
+                        "expected type \"{}\" found type \"{}\"",
+                        type_name,
+                        value.type_name()
+                    )));
+                }
+            }
+
+            let len = value_len(value).map_err(violation)?;
+            if let Some(max_len) = schema.max_len {
+                if len > max_len {
+                    return Err(violation(format!(
+                        "length {} exceeds the maximum length {}",
+                        len, max_len
+                    )));
+                }
+            }
+
+            if schema.required && len == 0 {
+                return Err(violation("value is required".to_string()));
+            }
+
+            if schema.unique {
+                let duplicate = self.iter().find(|(k, v)| {
+                    k.as_slice() != key
+                        && !except.contains(*k)
+                        && glob_match(k.as_slice(), schema.pattern.as_bytes())
+                        && v.type_name() == value.type_name()
+                        && *v == value
+                });
+
+                if let Some((k, _)) = duplicate {
+                    return Err(violation(format!(
+                        "value is not unique, it's already held by \"{}\"",
+                        k
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Length in bytes of strings, elements of lists, sets, sorted sets and hashes, bytes of other values
+///
+/// Err if a string or collection is too short to hold its length prefix.
+fn value_len(value: &BvObject) -> Result<usize, String> {
+    let collection = value.is_list() || value.is_set() || value.is_zset() || value.is_hash();
+    if (value.is_str() || collection) && value.len() < elements::COUNT_BS {
+        return Err(format!(
+            "value of {} bytes is too short for type \"{}\"",
+            value.len(),
+            value.type_name()
+        ));
+    }
+
+    Ok(if value.is_str() {
+        value.len() - elements::COUNT_BS
+    } else if collection {
+        elements::count(value.as_slice())
+    } else {
+        value.len()
+    })
+}
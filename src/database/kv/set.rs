@@ -0,0 +1,223 @@
+//! Set operations on KvDb
+//!
+//! Sets are stored as ordinary records of type "set", see [BvSet](../../../types/bv/bvset/struct.BvSet.html)
+
+use std::collections::BTreeSet;
+
+use super::types::Op;
+use super::KvDb;
+use crate::storage::KvInterface;
+use crate::types::bv::{bvset, elements, BvObject, BvSet, BvString};
+use crate::utils::serialize_object;
+
+impl<KV> KvDb<KV>
+where
+    KV: KvInterface<Key = BvString, Value = BvObject, RefKey = [u8]>,
+    for<'a> &'a KV: IntoIterator<Item = (&'a BvString, &'a BvObject)>,
+{
+    /// Add member to a set, the set is created if the key don't exists
+    ///
+    /// Returns false if the member already exists
+    pub fn sadd<S: AsRef<str>, T: serde::Serialize>(
+        &mut self,
+        key: S,
+        member: T,
+    ) -> Result<bool, String> {
+        let key = key.as_ref().as_bytes();
+        let member = serialize_object(&member);
+
+        match self.get_typed(key, bvset::SET_TYPE)? {
+            Some(set) if bvset::contains(set, &member) => Ok(false),
+            Some(_) => Ok(self
+                .modify_checked(Op::SAdd, key, |set| {
+                    BvSet::from(set).unwrap().insert(&member)
+                })?
+                .unwrap()),
+            None => {
+                let mut set = BvSet::new_object();
+                BvSet::from(&mut set).unwrap().insert(&member);
+                self.apply_checked(Op::SAdd, key, Some(set))?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Remove member from a set, the key is deleted when the set is empty
+    ///
+    /// Returns false if the member don't exists
+    pub fn srem<S: AsRef<str>, T: serde::Serialize>(
+        &mut self,
+        key: S,
+        member: T,
+    ) -> Result<bool, String> {
+        let key = key.as_ref().as_bytes();
+        let member = serialize_object(&member);
+
+        let len = match self.get_typed(key, bvset::SET_TYPE)? {
+            Some(set) if bvset::contains(set, &member) => elements::count(set.as_slice()),
+            _ => return Ok(false),
+        };
+
+        if len == 1 {
+            self.apply(Op::SRem, key, None);
+        } else {
+            self.modify_checked(Op::SRem, key, |set| {
+                BvSet::from(set).unwrap().remove(&member)
+            })?;
+        }
+
+        Ok(true)
+    }
+
+    /// Check if member exists in a set
+    ///
+    pub fn sismember<S: AsRef<str>, T: serde::Serialize>(
+        &self,
+        key: S,
+        member: T,
+    ) -> Result<bool, String> {
+        Ok(
+            match self.get_typed(key.as_ref().as_bytes(), bvset::SET_TYPE)? {
+                Some(set) => bvset::contains(set, &serialize_object(&member)),
+                None => false,
+            },
+        )
+    }
+
+    /// All members of a set, ordered by their serialized bytes
+    ///
+    pub fn smembers<S: AsRef<str>>(&self, key: S) -> Result<Vec<BvObject>, String> {
+        Ok(
+            match self.get_typed(key.as_ref().as_bytes(), bvset::SET_TYPE)? {
+                Some(set) => bvset::members(set),
+                None => Vec::new(),
+            },
+        )
+    }
+
+    /// Number of members in a set, 0 if the key don't exists
+    ///
+    pub fn scard<S: AsRef<str>>(&self, key: S) -> Result<usize, String> {
+        Ok(self
+            .get_typed(key.as_ref().as_bytes(), bvset::SET_TYPE)?
+            .map(|set| elements::count(set.as_slice()))
+            .unwrap_or(0))
+    }
+
+    /// Members existing in all sets, keys that don't exists are treated as empty sets
+    ///
+    pub fn sinter<S: AsRef<str>>(&self, keys: &[S]) -> Result<Vec<BvObject>, String> {
+        Ok(decode(self.inter(keys)?))
+    }
+
+    /// Members existing in any of the sets
+    ///
+    pub fn sunion<S: AsRef<str>>(&self, keys: &[S]) -> Result<Vec<BvObject>, String> {
+        Ok(decode(self.union(keys)?))
+    }
+
+    /// Members of the first set not existing in any of the other sets
+    ///
+    pub fn sdiff<S: AsRef<str>>(&self, keys: &[S]) -> Result<Vec<BvObject>, String> {
+        Ok(decode(self.diff(keys)?))
+    }
+
+    /// Store the intersection of sets in destination and return its size
+    ///
+    /// An empty result deletes destination.
+    pub fn sinterstore<S: AsRef<str>>(
+        &mut self,
+        destination: S,
+        keys: &[S],
+    ) -> Result<usize, String> {
+        let members = self.inter(keys)?;
+        self.store_set(destination.as_ref().as_bytes(), members)
+    }
+
+    /// Store the union of sets in destination and return its size
+    ///
+    /// An empty result deletes destination.
+    pub fn sunionstore<S: AsRef<str>>(
+        &mut self,
+        destination: S,
+        keys: &[S],
+    ) -> Result<usize, String> {
+        let members = self.union(keys)?;
+        self.store_set(destination.as_ref().as_bytes(), members)
+    }
+
+    /// Store the difference of sets in destination and return its size
+    ///
+    /// An empty result deletes destination.
+    pub fn sdiffstore<S: AsRef<str>>(
+        &mut self,
+        destination: S,
+        keys: &[S],
+    ) -> Result<usize, String> {
+        let members = self.diff(keys)?;
+        self.store_set(destination.as_ref().as_bytes(), members)
+    }
+
+    /// Serialized members of each set
+    fn encoded_sets<S: AsRef<str>>(&self, keys: &[S]) -> Result<Vec<Vec<Vec<u8>>>, String> {
+        keys.iter()
+            .map(|key| {
+                Ok(
+                    match self.get_typed(key.as_ref().as_bytes(), bvset::SET_TYPE)? {
+                        Some(set) => bvset::encoded_members(set),
+                        None => Vec::new(),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn inter<S: AsRef<str>>(&self, keys: &[S]) -> Result<Vec<Vec<u8>>, String> {
+        let mut sets = self.encoded_sets(keys)?.into_iter();
+        let mut members = sets.next().unwrap_or_default();
+
+        for set in sets {
+            members.retain(|m| set.binary_search(m).is_ok());
+        }
+
+        Ok(members)
+    }
+
+    fn union<S: AsRef<str>>(&self, keys: &[S]) -> Result<Vec<Vec<u8>>, String> {
+        Ok(self
+            .encoded_sets(keys)?
+            .into_iter()
+            .flatten()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect())
+    }
+
+    fn diff<S: AsRef<str>>(&self, keys: &[S]) -> Result<Vec<Vec<u8>>, String> {
+        let mut sets = self.encoded_sets(keys)?.into_iter();
+        let mut members = sets.next().unwrap_or_default();
+
+        for set in sets {
+            members.retain(|m| set.binary_search(m).is_err());
+        }
+
+        Ok(members)
+    }
+
+    fn store_set(&mut self, destination: &[u8], members: Vec<Vec<u8>>) -> Result<usize, String> {
+        let len = members.len();
+
+        if members.is_empty() {
+            self.apply(Op::SStore, destination, None);
+        } else {
+            self.apply_checked(Op::SStore, destination, Some(BvSet::from_encoded(&members)))?;
+            self.expiry.remove(destination);
+        }
+
+        Ok(len)
+    }
+}
+
+fn decode(members: Vec<Vec<u8>>) -> Vec<BvObject> {
+    members.iter().map(|m| elements::decode_object(m)).collect()
+}
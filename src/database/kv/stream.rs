@@ -0,0 +1,251 @@
+//! Stream operations on KvDb
+//!
+//! Streams are stored as ordinary records of type "stream", see [BvStream](../../../types/bv/bvstream/struct.BvStream.html).
+//! Consumer groups are stored in the stream value and persisted by `commit`.
+
+use super::types::Op;
+use super::{now_millis, KvDb};
+use crate::storage::KvInterface;
+use crate::types::bv::{bvstream, BvObject, BvStream, BvString, StreamGroup, StreamId};
+use crate::utils::serialize_object;
+
+impl<KV> KvDb<KV>
+where
+    KV: KvInterface<Key = BvString, Value = BvObject, RefKey = [u8]>,
+    for<'a> &'a KV: IntoIterator<Item = (&'a BvString, &'a BvObject)>,
+{
+    /// Append value to a stream, the stream is created if the key don't exists
+    ///
+    /// Returns the generated ID, IDs are the current time in milliseconds and a sequence
+    /// number, always greater than the last generated ID.
+    pub fn xadd<S: AsRef<str>, T: serde::Serialize>(
+        &mut self,
+        key: S,
+        value: T,
+    ) -> Result<StreamId, String> {
+        let key = key.as_ref().as_bytes();
+        let value = serialize_object(&value);
+
+        match self.get_typed(key, bvstream::STREAM_TYPE)? {
+            Some(_) => self
+                .modify_checked(Op::XAdd, key, |stream| {
+                    BvStream::from(stream).unwrap().push(now_millis(), &value)
+                })?
+                .unwrap(),
+            None => {
+                let mut stream = BvStream::new_object();
+                let id = BvStream::from(&mut stream)
+                    .unwrap()
+                    .push(now_millis(), &value)?;
+                self.apply_checked(Op::XAdd, key, Some(stream))?;
+                Ok(id)
+            }
+        }
+    }
+
+    /// Entries with start <= ID <= end, ordered by ID
+    ///
+    /// Use `StreamId::MIN` and `StreamId::MAX` for an open range.
+    pub fn xrange<S: AsRef<str>>(
+        &self,
+        key: S,
+        start: StreamId,
+        end: StreamId,
+    ) -> Result<Vec<(StreamId, BvObject)>, String> {
+        Ok(
+            match self.get_typed(key.as_ref().as_bytes(), bvstream::STREAM_TYPE)? {
+                Some(stream) => bvstream::range(stream, start, end),
+                None => Vec::new(),
+            },
+        )
+    }
+
+    /// Entries with start <= ID <= end, ordered by descending ID
+    ///
+    pub fn xrevrange<S: AsRef<str>>(
+        &self,
+        key: S,
+        end: StreamId,
+        start: StreamId,
+    ) -> Result<Vec<(StreamId, BvObject)>, String> {
+        let mut entries = self.xrange(key, start, end)?;
+        entries.reverse();
+        Ok(entries)
+    }
+
+    /// Number of entries in a stream, 0 if the key don't exists
+    ///
+    pub fn xlen<S: AsRef<str>>(&self, key: S) -> Result<usize, String> {
+        Ok(self
+            .get_typed(key.as_ref().as_bytes(), bvstream::STREAM_TYPE)?
+            .map(bvstream::len)
+            .unwrap_or(0))
+    }
+
+    /// Remove the oldest entries until at most `max_len` remains
+    ///
+    /// Returns the number of removed entries. The stream is kept even if it's emptied,
+    /// new IDs are still greater than the IDs of the removed entries.
+    pub fn xtrim<S: AsRef<str>>(&mut self, key: S, max_len: usize) -> Result<usize, String> {
+        let key = key.as_ref().as_bytes();
+
+        match self.get_typed(key, bvstream::STREAM_TYPE)? {
+            Some(stream) if bvstream::len(stream) > max_len => Ok(self
+                .modify_checked(Op::XTrim, key, |stream| {
+                    BvStream::from(stream).unwrap().trim(max_len)
+                })?
+                .unwrap()),
+            _ => Ok(0),
+        }
+    }
+
+    /// Create a consumer group delivering entries with an ID greater than `last_delivered`,
+    /// the stream is created if the key don't exists
+    ///
+    /// Use `StreamId::MIN` to deliver all entries, or the last ID of the stream to only deliver new entries.
+    pub fn xgroup_create<S: AsRef<str>, G: AsRef<str>>(
+        &mut self,
+        key: S,
+        group: G,
+        last_delivered: StreamId,
+    ) -> Result<(), String> {
+        let key = key.as_ref().as_bytes();
+        let mut groups = self.xgroups(key)?;
+
+        if groups.iter().any(|g| g.name == group.as_ref()) {
+            return Err(format!(
+                "Consumer group \"{}\" already exists",
+                group.as_ref()
+            ));
+        }
+
+        groups.push(StreamGroup {
+            name: group.as_ref().to_string(),
+            last_delivered,
+            pending: Vec::new(),
+        });
+
+        self.set_groups(key, &groups)?;
+        Ok(())
+    }
+
+    /// Remove a consumer group, returns false if it don't exists
+    ///
+    pub fn xgroup_destroy<S: AsRef<str>, G: AsRef<str>>(
+        &mut self,
+        key: S,
+        group: G,
+    ) -> Result<bool, String> {
+        let key = key.as_ref().as_bytes();
+        let mut groups = self.xgroups(key)?;
+        let len = groups.len();
+
+        groups.retain(|g| g.name != group.as_ref());
+        if groups.len() == len {
+            return Ok(false);
+        }
+
+        self.set_groups(key, &groups)?;
+        Ok(true)
+    }
+
+    /// Consumer groups of a stream
+    ///
+    pub fn xinfo_groups<S: AsRef<str>>(&self, key: S) -> Result<Vec<StreamGroup>, String> {
+        self.xgroups(key.as_ref().as_bytes())
+    }
+
+    /// Deliver at most `count` entries not yet delivered to the group
+    ///
+    /// The delivered entries are pending until acknowledged with `xack`.
+    pub fn xreadgroup<S: AsRef<str>, G: AsRef<str>>(
+        &mut self,
+        key: S,
+        group: G,
+        count: usize,
+    ) -> Result<Vec<(StreamId, BvObject)>, String> {
+        let key = key.as_ref().as_bytes();
+        let mut groups = self.xgroups(key)?;
+        let g = find_group(&mut groups, group.as_ref())?;
+
+        let entries = bvstream::after(self.records.get(key).unwrap(), g.last_delivered, count);
+        if let Some((id, _)) = entries.last() {
+            g.last_delivered = *id;
+            g.pending.extend(entries.iter().map(|(id, _)| *id));
+
+            self.modify_checked(Op::XReadGroup, key, |stream| {
+                BvStream::from(stream).unwrap().set_groups(&groups)
+            })?;
+        }
+
+        Ok(entries)
+    }
+
+    /// Acknowledge delivered entries, removing them from the pending entries of the group
+    ///
+    /// Returns the number of acknowledged entries
+    pub fn xack<S: AsRef<str>, G: AsRef<str>>(
+        &mut self,
+        key: S,
+        group: G,
+        ids: &[StreamId],
+    ) -> Result<usize, String> {
+        let key = key.as_ref().as_bytes();
+        let mut groups = self.xgroups(key)?;
+        let g = find_group(&mut groups, group.as_ref())?;
+
+        let len = g.pending.len();
+        g.pending.retain(|id| !ids.contains(id));
+        let acked = len - g.pending.len();
+
+        if acked > 0 {
+            self.modify_checked(Op::XAck, key, |stream| {
+                BvStream::from(stream).unwrap().set_groups(&groups)
+            })?;
+        }
+
+        Ok(acked)
+    }
+
+    /// Delivered entries not yet acknowledged by the group, ordered by ID
+    ///
+    pub fn xpending<S: AsRef<str>, G: AsRef<str>>(
+        &self,
+        key: S,
+        group: G,
+    ) -> Result<Vec<StreamId>, String> {
+        let mut groups = self.xgroups(key.as_ref().as_bytes())?;
+        Ok(find_group(&mut groups, group.as_ref())?.pending.clone())
+    }
+
+    fn xgroups(&self, key: &[u8]) -> Result<Vec<StreamGroup>, String> {
+        Ok(self
+            .get_typed(key, bvstream::STREAM_TYPE)?
+            .map(bvstream::groups)
+            .unwrap_or_default())
+    }
+
+    fn set_groups(&mut self, key: &[u8], groups: &[StreamGroup]) -> Result<(), String> {
+        if self.records.has_key(key) && !self.is_expired(key) {
+            self.modify_checked(Op::XGroup, key, |stream| {
+                BvStream::from(stream).unwrap().set_groups(groups)
+            })?;
+        } else {
+            let mut stream = BvStream::new_object();
+            BvStream::from(&mut stream).unwrap().set_groups(groups);
+            self.apply_checked(Op::XGroup, key, Some(stream))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn find_group<'a>(
+    groups: &'a mut [StreamGroup],
+    name: &str,
+) -> Result<&'a mut StreamGroup, String> {
+    groups
+        .iter_mut()
+        .find(|g| g.name == name)
+        .ok_or_else(|| format!("Consumer group \"{}\" does not exist", name))
+}
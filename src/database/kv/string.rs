@@ -0,0 +1,149 @@
+//! String operations on KvDb
+//!
+//! Operates on values of type "str", i.e a bincode serialized String or &str. Offsets and
+//! lengths are in bytes, the u64 length prefix of the value is kept up to date.
+
+use super::types::Op;
+use super::KvDb;
+use crate::storage::KvInterface;
+use crate::types::bv::{elements, BvObject, BvString};
+use crate::utils::serialize_object;
+
+const STR_TYPE: &str = "str";
+
+impl<KV> KvDb<KV>
+where
+    KV: KvInterface<Key = BvString, Value = BvObject, RefKey = [u8]>,
+    for<'a> &'a KV: IntoIterator<Item = (&'a BvString, &'a BvObject)>,
+{
+    /// Append value to a string, the string is created if the key don't exists
+    ///
+    /// Returns the length of the string
+    pub fn append<S: AsRef<str>>(&mut self, key: S, value: &str) -> Result<usize, String> {
+        let key = key.as_ref().as_bytes();
+
+        match self.get_typed(key, STR_TYPE)? {
+            Some(_) => Ok(self
+                .modify_checked(Op::Append, key, |s| {
+                    s.extend_from_slice(value.as_bytes());
+                    update_len(s)
+                })?
+                .unwrap()),
+            None => {
+                self.apply_checked(Op::Append, key, Some(serialize_object(value)))?;
+                Ok(value.len())
+            }
+        }
+    }
+
+    /// Substring from byte start to byte end(inclusive), negative indexes count from the end
+    ///
+    /// A missing key is treated as an empty string, Err if the range splits a character.
+    pub fn getrange<S: AsRef<str>>(
+        &self,
+        key: S,
+        start: isize,
+        end: isize,
+    ) -> Result<String, String> {
+        let s = match self.get_typed(key.as_ref().as_bytes(), STR_TYPE)? {
+            Some(s) => s.as_str(),
+            None => return Ok(String::new()),
+        };
+
+        match elements::normalize_range(start, end, s.len()) {
+            Some(range) => s
+                .get(range)
+                .map(|s| s.to_string())
+                .ok_or_else(|| "Range is not on character boundaries".to_string()),
+            None => Ok(String::new()),
+        }
+    }
+
+    /// Overwrite part of a string starting at byte offset, the string is zero padded to
+    /// offset and grows when needed
+    ///
+    /// A missing key is treated as an empty string, Err if the overwritten part splits a character
+    /// or its end overflows usize. Returns the length of the string
+    pub fn setrange<S: AsRef<str>>(
+        &mut self,
+        key: S,
+        offset: usize,
+        value: &str,
+    ) -> Result<usize, String> {
+        let key = key.as_ref().as_bytes();
+        let end = offset
+            .checked_add(value.len())
+            .ok_or_else(|| format!("Offset {} is too large", offset))?;
+
+        let edit = |s: &mut BvObject| {
+            let len = s.len() - 8;
+            if offset > len {
+                s.extend_from_slice(&vec![0; offset - len]);
+            }
+
+            s.splice(8 + offset..8 + end.min(len.max(offset)), value.as_bytes());
+            update_len(s)
+        };
+
+        match self.get_typed(key, STR_TYPE)? {
+            Some(s) => {
+                let s = s.as_str();
+                let boundary = |i: usize| i >= s.len() || s.is_char_boundary(i);
+                if !boundary(offset) || !boundary(end) {
+                    return Err("Range is not on character boundaries".to_string());
+                }
+
+                Ok(self.modify_checked(Op::SetRange, key, edit)?.unwrap())
+            }
+            None => {
+                let mut s = serialize_object("");
+                let len = edit(&mut s);
+                self.apply_checked(Op::SetRange, key, Some(s))?;
+                Ok(len)
+            }
+        }
+    }
+
+    /// Length of a string in bytes, 0 if the key don't exists
+    ///
+    pub fn strlen<S: AsRef<str>>(&self, key: S) -> Result<usize, String> {
+        Ok(self
+            .get_typed(key.as_ref().as_bytes(), STR_TYPE)?
+            .map(|s| s.len() - 8)
+            .unwrap_or(0))
+    }
+
+    /// Delete a string and return it
+    ///
+    pub fn getdel<S: AsRef<str>>(&mut self, key: S) -> Result<Option<String>, String> {
+        let key = key.as_ref().as_bytes();
+
+        if self.get_typed(key, STR_TYPE)?.is_none() {
+            return Ok(None);
+        }
+
+        Ok(self
+            .apply(Op::GetDel, key, None)
+            .map(|s| s.as_str().to_string()))
+    }
+
+    /// Set a string and return the previous string, the expiry time of the key is removed
+    ///
+    pub fn getset<S: AsRef<str>>(&mut self, key: S, value: &str) -> Result<Option<String>, String> {
+        let key = key.as_ref().as_bytes();
+
+        self.get_typed(key, STR_TYPE)?;
+        self.purge_expired(key);
+
+        let old = self.apply_checked(Op::GetSet, key, Some(serialize_object(value)))?;
+        self.expiry.remove(key);
+        Ok(old.map(|s| s.as_str().to_string()))
+    }
+}
+
+/// Write the byte length of a string to its u64 prefix, returns the length
+fn update_len(s: &mut BvObject) -> usize {
+    let len = s.len() - 8;
+    s[..8].copy_from_slice(&(len as u64).to_le_bytes());
+    len
+}
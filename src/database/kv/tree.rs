@@ -0,0 +1,78 @@
+//! Named keyspaces of KvDb
+//!
+//! A tree is a KvDb of its own with separate records, expiry, indexes, transactions and
+//! watchers, written to the same file as its parent. `commit` writes the parent and all
+//! trees, `commit_tree` a single tree. Transactions of the parent also cover its trees.
+
+use super::parser;
+use super::KvDb;
+use crate::fio;
+use crate::storage::KvInterface;
+use crate::types::bv::{BvObject, BvString};
+
+impl<KV: KvInterface> KvDb<KV> {
+    /// Open a tree, the tree is created if it don't exists
+    ///
+    /// Names may be at most 255 bytes.
+    pub fn open_tree<S: AsRef<str>>(&mut self, name: S) -> &mut KvDb<KV> {
+        assert!(!name.as_ref().is_empty() && name.as_ref().len() <= u8::MAX as usize);
+        self.trees.entry(name.as_ref().to_string()).or_default()
+    }
+
+    /// Retrieve an existing tree
+    ///
+    pub fn tree<S: AsRef<str>>(&self, name: S) -> Option<&KvDb<KV>> {
+        self.trees.get(name.as_ref())
+    }
+
+    /// Remove a tree and all of its records, returns false if it don't exists
+    ///
+    pub fn drop_tree<S: AsRef<str>>(&mut self, name: S) -> bool {
+        self.trees.remove(name.as_ref()).is_some()
+    }
+
+    /// Names of all trees, sorted
+    ///
+    pub fn list_trees(&self) -> Vec<&str> {
+        self.trees.keys().map(|name| name.as_str()).collect()
+    }
+
+    pub fn trees(&self) -> impl Iterator<Item = (&String, &KvDb<KV>)> {
+        self.trees.iter()
+    }
+}
+
+impl<KV> KvDb<KV>
+where
+    KV: KvInterface<Key = BvString, Value = BvObject, RefKey = [u8]>,
+    for<'a> &'a KV: IntoIterator<Item = (&'a BvString, &'a BvObject)>,
+{
+    /// Write a single tree to the database file, a dropped tree is removed from the file
+    ///
+    /// The parent and other trees are kept as they were last committed.
+    pub fn commit_tree<S: AsRef<str>>(&self, name: S) -> std::io::Result<()> {
+        self.check_file_name()?;
+        let (main, mut trees) = match std::fs::read(&self.file_name) {
+            Ok(buf) if buf.len() > fio::FILE_STAMP.len() => {
+                parser::split_trees(&buf[fio::FILE_STAMP.len()..])
+            }
+            Ok(_) => (Vec::new(), Vec::new()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (Vec::new(), Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        trees.retain(|(n, _)| n != name.as_ref());
+        if let Some(tree) = self.trees.get(name.as_ref()) {
+            trees.push((name.as_ref().to_string(), fio::encode_kv_db(tree)?));
+            trees.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+
+        let f = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.file_name)?;
+
+        fio::FileIO::new(f).commit_kv_sections(&main, &trees)
+    }
+}
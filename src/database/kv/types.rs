@@ -1 +1,453 @@
+use std::collections::{BTreeMap, BTreeSet};
 
+use serde::{Deserialize, Serialize};
+
+use crate::types::{BvObject, BvString};
+
+/// In-memory copy of a KvDb taken by transactions and savepoints
+///
+#[derive(Default, Clone)]
+pub struct Snapshot {
+    pub records: Vec<(BvString, BvObject)>,
+    pub expiry: Expiry,
+    pub indexes: IndexEntries,
+    pub trees: Vec<(String, Snapshot)>,
+}
+
+/// Non-record data read from or written to a database file
+///
+#[derive(Default)]
+pub struct Metadata {
+    /// Key, expiry time in milliseconds since UNIX_EPOCH
+    pub expires: Vec<(BvString, u64)>,
+    pub rules: Vec<KeyRule>,
+    pub schemas: Vec<KeySchema>,
+    /// Index definitions and the entries of field indexes, which can't be rebuilt
+    pub indexes: IndexEntries,
+    /// Named trees with their records and metadata, as written to the file
+    pub trees: Vec<(String, Vec<u8>)>,
+}
+
+/// Suffix generated for keys stored with `KvDb::store`
+///
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum KvRule {
+    /// 0, 1, 2...
+    AutoIncrement,
+    /// UUID version 4 format, e.g 9b2f0c5e-3f1a-4c1e-8d2b-5a6f7e8d9c0b
+    ///
+    /// The random bits come from the randomly keyed std hasher, not a cryptographic source.
+    /// Uniqueness across databases is best-effort, generated keys never collide with keys
+    /// in the same database.
+    Uuid,
+    /// Milliseconds since UNIX_EPOCH, incremented when stored within the same millisecond
+    Timestamp,
+}
+
+/// A key rule registered with `KvDb::key_config`, keys are generated as `<base><separator><suffix>`
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyRule {
+    pub base: String,
+    pub separator: String,
+    pub rule: KvRule,
+    /// Next auto increment number or the last generated timestamp
+    pub counter: u64,
+}
+
+/// Constraints on values of keys matching a glob style pattern, e.g "article:*:url"
+///
+/// ```
+/// use icbiadb::kv::types::KeySchema;
+///
+/// let schema = KeySchema::new("article:*:url").type_name("str").max_len(255).unique();
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeySchema {
+    pub pattern: String,
+    pub type_name: Option<String>,
+    /// Length in bytes of strings, elements of lists, sets, sorted sets and hashes,
+    /// bytes of other values
+    pub max_len: Option<usize>,
+    /// No two keys matching the pattern may hold equal values
+    pub unique: bool,
+    /// Values may not be empty
+    pub required: bool,
+}
+
+impl KeySchema {
+    pub fn new<S: AsRef<str>>(pattern: S) -> Self {
+        KeySchema {
+            pattern: pattern.as_ref().to_string(),
+            type_name: None,
+            max_len: None,
+            unique: false,
+            required: false,
+        }
+    }
+
+    pub fn type_name<S: AsRef<str>>(mut self, type_name: S) -> Self {
+        self.type_name = Some(type_name.as_ref().to_string());
+        self
+    }
+
+    pub fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    pub fn unique(mut self) -> Self {
+        self.unique = true;
+        self
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub fn optional(mut self) -> Self {
+        self.required = false;
+        self
+    }
+}
+
+/// Part of a value a secondary index is built on
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Extractor {
+    /// The whole value
+    Value,
+    /// A named field of a stored struct
+    Field(String),
+}
+
+/// A secondary index of values stored under keys starting with `prefix`
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexDef {
+    pub name: String,
+    pub prefix: String,
+    pub extractor: Extractor,
+}
+
+/// Index definitions with the sort keys of their entries, by key
+pub type IndexEntries = Vec<(IndexDef, Vec<(BvString, Vec<u8>)>)>;
+
+/// Key evicted when a cache exceeds its budget
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EvictionPolicy {
+    /// Least recently read or written
+    Lru,
+    /// Least frequently read or written, the least recently used of equally used keys
+    Lfu,
+    Random,
+    /// Nearest expiry time, keys without a time to live are never evicted
+    VolatileTtl,
+}
+
+/// Budget of a KvDb in cache mode, see `KvDb::set_cache`
+///
+/// ```
+/// use icbiadb::kv::types::{CacheConfig, EvictionPolicy};
+///
+/// let config = CacheConfig::new(EvictionPolicy::Lru).max_bytes(64 * 1024).max_keys(1000);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheConfig {
+    pub policy: EvictionPolicy,
+    /// Total byte length of keys, type names and values
+    pub max_bytes: Option<usize>,
+    pub max_keys: Option<usize>,
+}
+
+impl CacheConfig {
+    pub fn new(policy: EvictionPolicy) -> Self {
+        CacheConfig {
+            policy,
+            max_bytes: None,
+            max_keys: None,
+        }
+    }
+
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    pub fn max_keys(mut self, max_keys: usize) -> Self {
+        self.max_keys = Some(max_keys);
+        self
+    }
+}
+
+/// Usage of a KvDb in cache mode
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CacheStats {
+    pub bytes: usize,
+    pub keys: usize,
+    pub evictions: u64,
+}
+
+/// Keys grouped by the type name of their values
+///
+#[derive(Default, Clone)]
+pub struct TypeIndex {
+    types: BTreeMap<BvString, BTreeSet<BvString>>,
+}
+
+impl TypeIndex {
+    /// Move key from the old to the new type name, None if the key didn't/don't exist
+    pub fn update(&mut self, key: &[u8], old: Option<&BvString>, new: Option<&BvString>) {
+        if old == new {
+            return;
+        }
+
+        if let Some(old) = old {
+            if let Some(keys) = self.types.get_mut(old) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.types.remove(old);
+                }
+            }
+        }
+
+        if let Some(new) = new {
+            self.types
+                .entry(new.clone())
+                .or_default()
+                .insert(key.into());
+        }
+    }
+
+    /// Keys of type_name, sorted
+    pub fn keys(&self, type_name: &[u8]) -> impl Iterator<Item = &BvString> {
+        self.types.get(type_name).into_iter().flatten()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&BvString, &BTreeSet<BvString>)> {
+        self.types.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.types.clear();
+    }
+}
+
+/// Expiry times of keys, in milliseconds since UNIX_EPOCH
+///
+#[derive(Default, Clone)]
+pub struct Expiry {
+    deadlines: BTreeMap<BvString, u64>,
+    // Ordered by expiry time for sweeping
+    queue: BTreeSet<(u64, BvString)>,
+}
+
+impl Expiry {
+    pub fn is_empty(&self) -> bool {
+        self.deadlines.is_empty()
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<u64> {
+        self.deadlines.get(key).copied()
+    }
+
+    pub fn set(&mut self, key: &[u8], at: u64) {
+        self.remove(key);
+        self.deadlines.insert(key.into(), at);
+        self.queue.insert((at, key.into()));
+    }
+
+    pub fn remove(&mut self, key: &[u8]) -> Option<u64> {
+        let at = self.deadlines.remove(key)?;
+        self.queue.remove(&(at, key.into()));
+        Some(at)
+    }
+
+    pub fn is_expired(&self, key: &[u8], now: u64) -> bool {
+        match self.deadlines.get(key) {
+            Some(at) => *at <= now,
+            None => false,
+        }
+    }
+
+    /// Keys expired at `now`, the earliest expired first
+    pub fn expired(&self, now: u64) -> impl Iterator<Item = &BvString> {
+        self.queue
+            .iter()
+            .take_while(move |(at, _)| *at <= now)
+            .map(|(_, key)| key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&BvString, u64)> {
+        self.deadlines.iter().map(|(key, at)| (key, *at))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EventKind {
+    Insert,
+    Update,
+    Delete,
+    Expire,
+    Evict,
+}
+
+/// Change event delivered to watchers after a mutation has been applied
+///
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub kind: EventKind,
+    pub key: BvString,
+    pub old: Option<BvObject>,
+    pub new: Option<BvObject>,
+}
+
+/// Subscription on an exact key or a key prefix
+///
+pub struct Watcher {
+    key: Vec<u8>,
+    exact: bool,
+    sender: std::sync::mpsc::Sender<Event>,
+}
+
+impl Watcher {
+    pub fn new(key: &[u8], exact: bool, sender: std::sync::mpsc::Sender<Event>) -> Self {
+        Watcher {
+            key: key.to_vec(),
+            exact,
+            sender,
+        }
+    }
+
+    pub fn matches(&self, key: &[u8]) -> bool {
+        if self.exact {
+            self.key == key
+        } else {
+            key.starts_with(&self.key)
+        }
+    }
+
+    /// Returns false if the receiving end has been dropped
+    pub fn send(&self, event: Event) -> bool {
+        self.sender.send(event).is_ok()
+    }
+}
+
+/// Message delivered to subscribers of a channel
+///
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub channel: String,
+    pub payload: BvObject,
+}
+
+/// Subscription on an exact channel or a glob style channel pattern
+///
+pub struct Subscriber {
+    channel: String,
+    pattern: bool,
+    sender: std::sync::mpsc::Sender<Message>,
+}
+
+impl Subscriber {
+    pub fn new(channel: &str, pattern: bool, sender: std::sync::mpsc::Sender<Message>) -> Self {
+        Subscriber {
+            channel: channel.to_string(),
+            pattern,
+            sender,
+        }
+    }
+
+    pub fn matches(&self, channel: &str) -> bool {
+        if self.pattern {
+            crate::slice::glob_match(channel.as_bytes(), self.channel.as_bytes())
+        } else {
+            self.channel == channel
+        }
+    }
+
+    /// Returns false if the receiving end has been dropped
+    pub fn send(&self, message: Message) -> bool {
+        self.sender.send(message).is_ok()
+    }
+}
+
+/// Journaled operations
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Set,
+    Del,
+    Swap,
+    Incr,
+    Decr,
+    LPush,
+    RPush,
+    LPop,
+    RPop,
+    LSet,
+    LTrim,
+    SAdd,
+    SRem,
+    SStore,
+    ZAdd,
+    ZIncrBy,
+    ZRem,
+    HSet,
+    HDel,
+    HIncrBy,
+    XAdd,
+    XTrim,
+    XGroup,
+    XReadGroup,
+    XAck,
+    SetBit,
+    BitOp,
+    PfAdd,
+    PfMerge,
+    Append,
+    SetRange,
+    GetDel,
+    GetSet,
+    Rename,
+    Copy,
+    Compute,
+    Merge,
+    UpdateTuple,
+}
+
+/// Bitwise operation of `KvDb::bitop`
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BitOp {
+    And,
+    Or,
+    Xor,
+    Not,
+}
+
+/// A journaled operation, undo writes `old` back and redo writes `new`
+///
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    pub op: Op,
+    pub key: BvString,
+    pub old: Option<BvObject>,
+    pub new: Option<BvObject>,
+    pub old_meta: KeyMeta,
+    /// Captured when the entry is undone
+    pub new_meta: KeyMeta,
+}
+
+/// Expiry and field index entries of a key, restored along with its value by undo and redo
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KeyMeta {
+    /// Milliseconds since UNIX_EPOCH
+    pub expires_at: Option<u64>,
+    /// Sort keys by index name
+    pub fields: Vec<(String, Vec<u8>)>,
+}
@@ -0,0 +1,194 @@
+//! Sorted set operations on KvDb
+//!
+//! Sorted sets are stored as ordinary records of type "zset", see [BvZSet](../../../types/bv/bvzset/struct.BvZSet.html)
+
+use super::types::Op;
+use super::KvDb;
+use crate::storage::KvInterface;
+use crate::types::bv::{bvzset, elements, BvObject, BvString, BvZSet};
+use crate::utils::serialize_object;
+
+impl<KV> KvDb<KV>
+where
+    KV: KvInterface<Key = BvString, Value = BvObject, RefKey = [u8]>,
+    for<'a> &'a KV: IntoIterator<Item = (&'a BvString, &'a BvObject)>,
+{
+    /// Add member with score to a sorted set, the sorted set is created if the key don't exists
+    ///
+    /// Updates the score of an existing member and returns false.
+    pub fn zadd<S: AsRef<str>, T: serde::Serialize>(
+        &mut self,
+        key: S,
+        score: f64,
+        member: T,
+    ) -> Result<bool, String> {
+        if score.is_nan() {
+            return Err("Score is not a number".to_string());
+        }
+
+        let key = key.as_ref().as_bytes();
+        let member = serialize_object(&member);
+
+        match self.get_typed(key, bvzset::ZSET_TYPE)? {
+            Some(zset) if bvzset::score(zset, &member) == Some(score) => Ok(false),
+            Some(_) => Ok(self
+                .modify_checked(Op::ZAdd, key, |zset| {
+                    BvZSet::from(zset).unwrap().insert(&member, score)
+                })?
+                .unwrap()),
+            None => {
+                let mut zset = BvZSet::new_object();
+                BvZSet::from(&mut zset).unwrap().insert(&member, score);
+                self.apply_checked(Op::ZAdd, key, Some(zset))?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Increment the score of member, a missing member is added with a score of `increment`
+    ///
+    /// Returns the new score
+    pub fn zincrby<S: AsRef<str>, T: serde::Serialize>(
+        &mut self,
+        key: S,
+        increment: f64,
+        member: T,
+    ) -> Result<f64, String> {
+        let key = key.as_ref();
+        let score = self
+            .zscore(key, &member)?
+            .map_or(increment, |score| score + increment);
+
+        if score.is_nan() {
+            return Err("Resulting score is not a number".to_string());
+        }
+
+        let exists = self.has_key(key);
+        let (key, member) = (key.as_bytes(), serialize_object(&member));
+
+        if exists {
+            self.modify_checked(Op::ZIncrBy, key, |zset| {
+                BvZSet::from(zset).unwrap().insert(&member, score)
+            })?;
+        } else {
+            let mut zset = BvZSet::new_object();
+            BvZSet::from(&mut zset).unwrap().insert(&member, score);
+            self.apply_checked(Op::ZIncrBy, key, Some(zset))?;
+        }
+
+        Ok(score)
+    }
+
+    /// Score of member, None if the member or key don't exists
+    ///
+    pub fn zscore<S: AsRef<str>, T: serde::Serialize>(
+        &self,
+        key: S,
+        member: T,
+    ) -> Result<Option<f64>, String> {
+        Ok(self
+            .get_typed(key.as_ref().as_bytes(), bvzset::ZSET_TYPE)?
+            .and_then(|zset| bvzset::score(zset, &serialize_object(&member))))
+    }
+
+    /// Position of member ordered by ascending score
+    ///
+    pub fn zrank<S: AsRef<str>, T: serde::Serialize>(
+        &self,
+        key: S,
+        member: T,
+    ) -> Result<Option<usize>, String> {
+        Ok(self
+            .get_typed(key.as_ref().as_bytes(), bvzset::ZSET_TYPE)?
+            .and_then(|zset| bvzset::rank(zset, &serialize_object(&member))))
+    }
+
+    /// Number of members in a sorted set, 0 if the key don't exists
+    ///
+    pub fn zcard<S: AsRef<str>>(&self, key: S) -> Result<usize, String> {
+        Ok(self
+            .get_typed(key.as_ref().as_bytes(), bvzset::ZSET_TYPE)?
+            .map(|zset| elements::count(zset.as_slice()))
+            .unwrap_or(0))
+    }
+
+    /// Members and scores from start to stop(inclusive) ordered by ascending score,
+    /// negative indexes count from the end
+    ///
+    pub fn zrange<S: AsRef<str>>(
+        &self,
+        key: S,
+        start: isize,
+        stop: isize,
+    ) -> Result<Vec<(BvObject, f64)>, String> {
+        match self.get_typed(key.as_ref().as_bytes(), bvzset::ZSET_TYPE)? {
+            Some(zset) => Ok(bvzset::range(zset, start, stop)),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Members and scores from start to stop(inclusive) ordered by descending score,
+    /// negative indexes count from the end
+    ///
+    pub fn zrevrange<S: AsRef<str>>(
+        &self,
+        key: S,
+        start: isize,
+        stop: isize,
+    ) -> Result<Vec<(BvObject, f64)>, String> {
+        let len = self.zcard(key.as_ref())? as isize;
+        let abs = |i: isize| if i < 0 { len + i } else { i };
+        let (start, stop) = (abs(start).max(0), abs(stop).min(len - 1));
+        if start > stop {
+            return Ok(Vec::new());
+        }
+
+        // Index i from the end is index len - 1 - i from the start
+        let mut members = self.zrange(key, len - 1 - stop, len - 1 - start)?;
+        members.reverse();
+        Ok(members)
+    }
+
+    /// Members and scores with min <= score <= max, ordered by ascending score
+    ///
+    pub fn zrangebyscore<S: AsRef<str>>(
+        &self,
+        key: S,
+        min: f64,
+        max: f64,
+    ) -> Result<Vec<(BvObject, f64)>, String> {
+        match self.get_typed(key.as_ref().as_bytes(), bvzset::ZSET_TYPE)? {
+            Some(zset) => Ok(bvzset::range_by_score(zset, min, max)),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Remove member from a sorted set, the key is deleted when the sorted set is empty
+    ///
+    /// Returns false if the member don't exists
+    pub fn zrem<S: AsRef<str>, T: serde::Serialize>(
+        &mut self,
+        key: S,
+        member: T,
+    ) -> Result<bool, String> {
+        let key = key.as_ref().as_bytes();
+        let member = serialize_object(&member);
+
+        let len = match self.get_typed(key, bvzset::ZSET_TYPE)? {
+            Some(zset) if bvzset::score(zset, &member).is_some() => {
+                elements::count(zset.as_slice())
+            }
+            _ => return Ok(false),
+        };
+
+        if len == 1 {
+            self.apply(Op::ZRem, key, None);
+        } else {
+            self.modify_checked(Op::ZRem, key, |zset| {
+                BvZSet::from(zset).unwrap().remove(&member)
+            })?;
+        }
+
+        Ok(true)
+    }
+}
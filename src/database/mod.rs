@@ -1,6 +1,8 @@
 pub mod doc;
+pub mod journal;
 pub mod kv;
 pub mod table;
+pub mod transaction;
 
 pub use doc::DocDb;
 pub use kv::KvDb;
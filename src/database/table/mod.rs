@@ -5,7 +5,10 @@ pub mod parser;
 pub mod types;
 
 use std::io::{BufReader, Seek, SeekFrom};
+use std::sync::mpsc;
 
+use crate::database::journal::Journal;
+use crate::database::transaction::Savepoints;
 use crate::fio;
 use types::*;
 
@@ -14,6 +17,9 @@ pub fn mem() -> TableDb {
         file_name: String::new(),
         maps: TableMap::default(),
         rows: TableRows::default(),
+        savepoints: Savepoints::default(),
+        watchers: Vec::new(),
+        journal: None,
     }
 }
 
@@ -31,6 +37,9 @@ pub fn create(file_name: &str) -> std::io::Result<TableDb> {
             file_name: file_name.to_string(),
             maps: TableMap::default(),
             rows: TableRows::default(),
+            savepoints: Savepoints::default(),
+            watchers: Vec::new(),
+            journal: None,
         });
     }
 
@@ -56,6 +65,9 @@ pub fn create(file_name: &str) -> std::io::Result<TableDb> {
         file_name: file_name.to_string(),
         maps: tmaps,
         rows: trows,
+        savepoints: Savepoints::default(),
+        watchers: Vec::new(),
+        journal: None,
     })
 }
 
@@ -64,6 +76,9 @@ pub struct TableDb {
     pub file_name: String,
     pub maps: TableMap,
     pub rows: TableRows,
+    savepoints: Savepoints<(TableMap, TableRows)>,
+    watchers: Vec<(Vec<u8>, mpsc::Sender<RowEvent>)>,
+    journal: Option<Journal<InsertEntry>>,
 }
 
 impl TableDb {
@@ -80,6 +95,64 @@ impl TableDb {
         Ok(())
     }
 
+    /// Open a transaction, calling begin inside a transaction opens a nested one
+    ///
+    /// Transactions only live in memory, commit writes the current state whether
+    /// a transaction is open or not.
+    pub fn begin(&mut self) {
+        self.savepoints
+            .begin((self.maps.clone(), self.rows.clone()));
+    }
+
+    /// Close the innermost transaction and keep its changes
+    ///
+    pub fn end(&mut self) -> Result<(), String> {
+        self.savepoints.end().map(|_| ())
+    }
+
+    /// Close the innermost transaction and discard its changes
+    ///
+    pub fn rollback(&mut self) -> Result<(), String> {
+        let (maps, rows) = self.savepoints.end()?;
+        self.maps = maps;
+        self.rows = rows;
+        self.clear_journal();
+        Ok(())
+    }
+
+    /// Create a named savepoint
+    ///
+    pub fn savepoint<S: AsRef<str>>(&mut self, name: S) {
+        self.savepoints
+            .savepoint(name, (self.maps.clone(), self.rows.clone()));
+    }
+
+    /// Discard all changes made after savepoint `name`, the savepoint is kept
+    ///
+    /// Rows inserted after the savepoint are removed as well, so their unique
+    /// values no longer collide with later inserts. Err if a transaction was begun after
+    /// the savepoint and is still open.
+    pub fn rollback_to<S: AsRef<str>>(&mut self, name: S) -> Result<(), String> {
+        let (maps, rows) = self.savepoints.rollback_to(name)?;
+        self.maps = maps.clone();
+        self.rows = rows.clone();
+        self.clear_journal();
+        Ok(())
+    }
+
+    /// Remove savepoint `name` and all savepoints created after it, keeping their changes
+    ///
+    /// Err if a transaction was begun after the savepoint and is still open.
+    pub fn release<S: AsRef<str>>(&mut self, name: S) -> Result<(), String> {
+        self.savepoints.release(name)
+    }
+
+    /// Check if a transaction or savepoint is active
+    ///
+    pub fn in_transaction(&self) -> bool {
+        self.savepoints.is_active()
+    }
+
     pub fn exists<S: AsRef<str>>(&self, name: S) -> bool {
         self.maps.contains_key(name.as_ref().as_bytes())
     }
@@ -148,9 +221,11 @@ impl TableDb {
             }
         }
 
+        let index = rows.len();
         self.rows
             .entry(name.as_ref().as_bytes().to_vec())
-            .and_modify(|v| v.push(row));
+            .and_modify(|v| v.push(row.clone()));
+        self.inserted(name.as_ref(), index, vec![row]);
         Ok(())
     }
 
@@ -190,11 +265,116 @@ impl TableDb {
             }
         }
 
-        rows.extend(new_rows);
+        let index = rows.len();
+        rows.extend(new_rows.iter().cloned());
+        self.inserted(name.as_ref(), index, new_rows);
 
         Ok(())
     }
 
+    /// Watch a table for row inserts
+    ///
+    pub fn watch<S: AsRef<str>>(&mut self, name: S) -> mpsc::Receiver<RowEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.watchers
+            .push((name.as_ref().as_bytes().to_vec(), sender));
+        receiver
+    }
+
+    /// Keep an undo/redo journal of at most `depth` row inserts
+    ///
+    /// Rolling back a transaction clears the journal.
+    pub fn enable_journal(&mut self, depth: usize) {
+        self.journal = Some(Journal::new(depth));
+    }
+
+    pub fn disable_journal(&mut self) {
+        self.journal = None;
+    }
+
+    /// Remove the latest journaled rows and return the entry
+    ///
+    /// None if there's nothing to undo or the table no longer holds the journaled rows,
+    /// e.g. it has been removed and recreated. Such entries are dropped.
+    pub fn undo(&mut self) -> Option<InsertEntry> {
+        let journal = self.journal.as_mut()?;
+        let entry = journal.undo()?;
+
+        let end = entry.index + entry.rows.len();
+        match self.rows.get_mut(entry.table.as_bytes()) {
+            Some(rows) if end <= rows.len() => {
+                rows.drain(entry.index..end);
+            }
+            _ => return None,
+        }
+
+        journal.undone(entry.clone());
+        Some(entry)
+    }
+
+    /// Insert the latest undone rows again and return the entry
+    ///
+    /// None if there's nothing to redo or the rows can't be inserted at their old position.
+    pub fn redo(&mut self) -> Option<InsertEntry> {
+        let journal = self.journal.as_mut()?;
+        let entry = journal.redo()?;
+
+        match self.rows.get_mut(entry.table.as_bytes()) {
+            Some(rows) if entry.index <= rows.len() => {
+                let tail = rows.split_off(entry.index);
+                rows.extend(entry.rows.iter().cloned());
+                rows.extend(tail);
+            }
+            _ => return None,
+        }
+
+        journal.redone(entry.clone());
+        self.notify(&entry.table, &entry.rows);
+        Some(entry)
+    }
+
+    /// Undoable inserts, oldest first
+    ///
+    pub fn history(&self) -> Vec<&InsertEntry> {
+        match &self.journal {
+            Some(journal) => journal.history().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn clear_journal(&mut self) {
+        if let Some(journal) = self.journal.as_mut() {
+            journal.clear();
+        }
+    }
+
+    fn inserted(&mut self, name: &str, index: usize, rows: Vec<TableRow>) {
+        self.notify(name, &rows);
+
+        if let Some(journal) = self.journal.as_mut() {
+            journal.record(InsertEntry {
+                table: name.to_string(),
+                index,
+                rows,
+            });
+        }
+    }
+
+    fn notify(&mut self, name: &str, rows: &[TableRow]) {
+        // Drop watchers whose receiver is gone
+        self.watchers.retain(|(table, sender)| {
+            table.as_slice() != name.as_bytes()
+                || rows.iter().all(|row| {
+                    sender
+                        .send(RowEvent {
+                            table: name.to_string(),
+                            row: row.clone(),
+                        })
+                        .is_ok()
+                })
+        });
+    }
+
     pub fn query<S: AsRef<str>>(&self, name: S) -> QueryBuilder {
         let table_map = &self.maps[name.as_ref().as_bytes()];
         let rows = &self.rows[name.as_ref().as_bytes()];
@@ -102,6 +102,21 @@ impl std::cmp::PartialEq for TableRow {
     }
 }
 
+/// Row event delivered to table watchers after the row has been inserted
+#[derive(Debug, Clone)]
+pub struct RowEvent {
+    pub table: String,
+    pub row: TableRow,
+}
+
+/// Journaled row insert, `rows` were inserted into `table` starting at `index`
+#[derive(Debug, Clone)]
+pub struct InsertEntry {
+    pub table: String,
+    pub index: usize,
+    pub rows: Vec<TableRow>,
+}
+
 /// Stores the records of a group declaration
 ///
 /// Used by mem::Memory
@@ -0,0 +1,118 @@
+//! Savepoint stack shared by the database implementations
+//!
+//! A transaction is opened with `begin()`, which pushes an unnamed savepoint.
+//! Calling `begin()` again opens a nested transaction. Named savepoints can be
+//! pushed anywhere inside a transaction and rolled back to or released by name.
+//!
+
+/// A snapshot of the database state taken when the savepoint was created
+pub struct Savepoint<S> {
+    name: Option<String>,
+    state: S,
+}
+
+impl<S> Savepoint<S> {
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+}
+
+pub struct Savepoints<S> {
+    stack: Vec<Savepoint<S>>,
+}
+
+impl<S> Default for Savepoints<S> {
+    fn default() -> Self {
+        Savepoints { stack: Vec::new() }
+    }
+}
+
+impl<S> Savepoints<S> {
+    /// Check if any transaction or savepoint is active
+    ///
+    pub fn is_active(&self) -> bool {
+        !self.stack.is_empty()
+    }
+
+    /// Number of open (nested) transactions, savepoints excluded
+    ///
+    pub fn depth(&self) -> usize {
+        self.stack.iter().filter(|sp| sp.name.is_none()).count()
+    }
+
+    /// Open a transaction level
+    ///
+    pub fn begin(&mut self, state: S) {
+        self.stack.push(Savepoint { name: None, state });
+    }
+
+    /// Push a named savepoint, a previous savepoint with the same name is shadowed
+    ///
+    pub fn savepoint<N: AsRef<str>>(&mut self, name: N, state: S) {
+        self.stack.push(Savepoint {
+            name: Some(name.as_ref().to_string()),
+            state,
+        });
+    }
+
+    /// Discard every savepoint created after `name` and return the state of `name`,
+    /// the savepoint itself is kept so it can be rolled back to again
+    ///
+    /// Err if a transaction level was opened after `name`, end or roll it back first.
+    pub fn rollback_to<N: AsRef<str>>(&mut self, name: N) -> Result<&S, String> {
+        let idx = self.position(name.as_ref())?;
+        self.stack.truncate(idx + 1);
+        Ok(&self.stack[idx].state)
+    }
+
+    /// Remove `name` and every savepoint created after it, keeping their changes
+    ///
+    /// Err if a transaction level was opened after `name`, end it first.
+    pub fn release<N: AsRef<str>>(&mut self, name: N) -> Result<(), String> {
+        let idx = self.position(name.as_ref())?;
+        self.stack.truncate(idx);
+        Ok(())
+    }
+
+    /// Close the innermost transaction level and return its state,
+    /// savepoints created inside of it are discarded
+    ///
+    pub fn end(&mut self) -> Result<S, String> {
+        let idx = self
+            .stack
+            .iter()
+            .rposition(|sp| sp.name.is_none())
+            .ok_or_else(|| "No active transaction".to_string())?;
+
+        self.stack.truncate(idx + 1);
+        Ok(self.stack.pop().unwrap().state)
+    }
+
+    /// Drop all transactions and savepoints
+    ///
+    pub fn clear(&mut self) {
+        self.stack.clear();
+    }
+
+    /// Index of the savepoint `name`, Err if a transaction level was opened after it
+    fn position(&self, name: &str) -> Result<usize, String> {
+        let idx = self
+            .stack
+            .iter()
+            .rposition(|sp| sp.name.as_deref() == Some(name))
+            .ok_or_else(|| format!("Savepoint \"{}\" does not exist", name))?;
+
+        if self.stack[idx..].iter().any(|sp| sp.name.is_none()) {
+            return Err(format!(
+                "Savepoint \"{}\" is outside of the innermost transaction",
+                name
+            ));
+        }
+
+        Ok(idx)
+    }
+}
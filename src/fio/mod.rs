@@ -2,7 +2,7 @@ pub mod reader;
 pub mod writer;
 
 use std::{
-    io::{BufWriter, Seek, SeekFrom, Write},
+    io::{BufWriter, Cursor, Seek, SeekFrom, Write},
     sync::RwLock,
 };
 
@@ -38,9 +38,24 @@ impl<W: Write + Seek> FileIO<W> {
     {
         let mut writer = self.writer.write().unwrap();
         writer.write_all(FILE_STAMP)?;
+        write_kv_db(&mut writer, kv)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Write an already serialized main keyspace and trees
+    pub fn commit_kv_sections(
+        &mut self,
+        main: &[u8],
+        trees: &[(String, Vec<u8>)],
+    ) -> std::io::Result<()> {
+        let mut writer = self.writer.write().unwrap();
+        writer.write_all(FILE_STAMP)?;
+        writer.write_all(main)?;
 
-        for record in (&kv.records).into_iter() {
-            writer.write_kv_record(record)?;
+        for (name, section) in trees {
+            writer.write_kv_tree(name, section)?;
         }
 
         writer.flush()?;
@@ -91,3 +106,49 @@ impl<W: Write + Seek> FileIO<W> {
         Ok(())
     }
 }
+
+/// Records, metadata and trees of a KvDb as written after the file stamp
+pub fn encode_kv_db<KV: KvInterface>(kv: &KvDb<KV>) -> std::io::Result<Vec<u8>>
+where
+    for<'a> &'a KV: IntoIterator<Item = (&'a BvString, &'a BvObject)>,
+{
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    write_kv_db(&mut writer, kv)?;
+
+    Ok(writer.writer.into_inner())
+}
+
+fn write_kv_db<W: Write + Seek, KV: KvInterface>(
+    writer: &mut Writer<W>,
+    kv: &KvDb<KV>,
+) -> std::io::Result<()>
+where
+    for<'a> &'a KV: IntoIterator<Item = (&'a BvString, &'a BvObject)>,
+{
+    for record in kv.iter() {
+        writer.write_kv_record(record)?;
+    }
+
+    let metadata = kv.metadata();
+    for (key, at) in metadata.expires.iter() {
+        writer.write_kv_expire(key, *at)?;
+    }
+
+    for rule in metadata.rules.iter() {
+        writer.write_kv_rule(rule)?;
+    }
+
+    for schema in metadata.schemas.iter() {
+        writer.write_kv_schema(schema)?;
+    }
+
+    for (def, entries) in metadata.indexes.iter() {
+        writer.write_kv_index(def, entries)?;
+    }
+
+    for (name, tree) in kv.trees() {
+        writer.write_kv_tree(name, &encode_kv_db(tree)?)?;
+    }
+
+    Ok(())
+}
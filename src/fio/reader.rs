@@ -7,7 +7,7 @@ use crate::utils::*;
 use super::FILE_STAMP;
 
 use crate::database::{
-    kv::parser::extract_records,
+    kv::{parser::extract_db, types::Metadata},
     table::parser::{extract_tables, rows::extract_rows},
 };
 
@@ -64,15 +64,21 @@ impl<T: std::io::BufRead + std::io::Seek> Reader<T> {
     pub fn read_kv_records<KV: KvInterface<Key = BvString, Value = BvObject, RefKey = [u8]>>(
         &mut self,
     ) -> std::io::Result<KV> {
+        Ok(self.read_kv_db()?.0)
+    }
+
+    pub fn read_kv_db<KV: KvInterface<Key = BvString, Value = BvObject, RefKey = [u8]>>(
+        &mut self,
+    ) -> std::io::Result<(KV, Metadata)> {
         let mut dbuf = Vec::new();
         self.reader.read_to_end(&mut dbuf)?;
 
         if dbuf.len() <= FILE_STAMP.len() {
-            Ok(KV::default())
+            Ok((KV::default(), Metadata::default()))
         } else {
             #[cfg(test)]
             debug!("[Reading kv records] Read {}", FILE_STAMP.len());
-            Ok(extract_records(&dbuf[FILE_STAMP.len()..]))
+            Ok(extract_db(&dbuf[FILE_STAMP.len()..]))
         }
     }
 
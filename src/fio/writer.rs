@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::io::SeekFrom;
 
 use crate::byte_size::globals::*;
+use crate::database::kv::types::{IndexDef, KeyRule, KeySchema};
 use crate::database::table::types::*;
 use crate::types::bv::{BvObject, BvString};
 use crate::utils::serialize;
@@ -110,6 +111,73 @@ impl<T: std::io::Write + std::io::Seek> Writer<T> {
         Ok(length)
     }
 
+    pub fn write_kv_expire(&mut self, key: &BvString, at: u64) -> std::io::Result<u64> {
+        // Identifier, key length, expiry time, key
+        assert!(key.len() as u8 > 0);
+        let mut length = 0;
+
+        length += self.writer.write(&kv::EXPIRE_IDENT)? as u64;
+        length += self.writer.write(&[key.len() as u8])? as u64;
+        length += self.writer.write(&serialize(&at))? as u64;
+        length += self.writer.write(key.as_slice())? as u64;
+
+        Ok(length)
+    }
+
+    pub fn write_kv_rule(&mut self, rule: &KeyRule) -> std::io::Result<u64> {
+        // Identifier, rule length, rule
+        let ser_rule = serialize(rule);
+        let mut length = 0;
+
+        length += self.writer.write(&kv::RULE_IDENT)? as u64;
+        length += self.writer.write(&serialize(&(ser_rule.len() as u32)))? as u64;
+        length += self.writer.write(&ser_rule)? as u64;
+
+        Ok(length)
+    }
+
+    pub fn write_kv_schema(&mut self, schema: &KeySchema) -> std::io::Result<u64> {
+        // Identifier, schema length, schema
+        let ser_schema = serialize(schema);
+        let mut length = 0;
+
+        length += self.writer.write(&kv::SCHEMA_IDENT)? as u64;
+        length += self.writer.write(&serialize(&(ser_schema.len() as u32)))? as u64;
+        length += self.writer.write(&ser_schema)? as u64;
+
+        Ok(length)
+    }
+
+    pub fn write_kv_index(
+        &mut self,
+        def: &IndexDef,
+        entries: &[(BvString, Vec<u8>)],
+    ) -> std::io::Result<u64> {
+        // Identifier, index length, definition and entries
+        let ser_index = serialize(&(def, entries));
+        let mut length = 0;
+
+        length += self.writer.write(&kv::INDEX_IDENT)? as u64;
+        length += self.writer.write(&serialize(&(ser_index.len() as u32)))? as u64;
+        length += self.writer.write(&ser_index)? as u64;
+
+        Ok(length)
+    }
+
+    pub fn write_kv_tree(&mut self, name: &str, section: &[u8]) -> std::io::Result<u64> {
+        // Identifier, name length, section length, name, section
+        let mut length = 0;
+
+        length += self.writer.write(&kv::TREE_IDENT)? as u64;
+        length += self.writer.write(&[name.len() as u8])? as u64;
+        length += self.writer.write(&serialize(&(section.len() as u64)))? as u64;
+        length += self.writer.write(name.as_bytes())? as u64;
+        self.writer.write_all(section)?;
+        length += section.len() as u64;
+
+        Ok(length)
+    }
+
     pub fn write_decl_header(&mut self) -> std::io::Result<u64> {
         let mut length = 0;
         length += self.writer.write(&table::rows::IDENT)? as u64;
@@ -10,7 +10,7 @@
 //! use icbiadb::storage::BTreeMap;
 //!
 //! let mut db = icbiadb::kv::create::<BTreeMap>("my_kvs.idb").unwrap();
-//! db.set("hello:world", 100);
+//! db.set("hello:world", 100).unwrap();
 //! db.commit();
 //! ```
 //! See [KvDb](database/kv/struct.KvDb.html) for all methods.
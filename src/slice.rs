@@ -115,3 +115,81 @@ pub fn contains_sequence(v: &[u8], seq: &[u8]) -> bool {
 
     false
 }
+
+/// Match v against a glob style pattern
+///
+/// `*` matches any sequence, `?` any single byte, `[abc]`, `[a-z]` and `[^abc]` a set of bytes,
+/// `\` escapes the next byte.
+pub fn glob_match(v: &[u8], pattern: &[u8]) -> bool {
+    let (mut vi, mut pi) = (0, 0);
+    // Position after the last `*` and the byte of v it's currently matched up to
+    let mut star: Option<(usize, usize)> = None;
+
+    while vi < v.len() {
+        let step = match pattern.get(pi) {
+            Some(b'*') => {
+                star = Some((pi + 1, vi));
+                pi += 1;
+                continue;
+            }
+            Some(b'?') => Some(1),
+            Some(b'[') => match_class(&pattern[pi..], v[vi]),
+            Some(b'\\') if pi + 1 < pattern.len() => {
+                if pattern[pi + 1] == v[vi] {
+                    Some(2)
+                } else {
+                    None
+                }
+            }
+            Some(b) if *b == v[vi] => Some(1),
+            _ => None,
+        };
+
+        match (step, star) {
+            (Some(step), _) => {
+                pi += step;
+                vi += 1;
+            }
+            (None, Some((star_pi, star_vi))) => {
+                pi = star_pi;
+                vi = star_vi + 1;
+                star = Some((star_pi, vi));
+            }
+            (None, None) => return false,
+        }
+    }
+
+    pattern[pi..].iter().all(|b| *b == b'*')
+}
+
+/// Match b against the class at the start of pattern, returns the length of the class on a match
+fn match_class(pattern: &[u8], b: u8) -> Option<usize> {
+    let end = match pattern.iter().skip(1).position(|c| *c == b']') {
+        Some(i) => i + 1,
+        // An unterminated class is a literal `[`
+        None => return if b == b'[' { Some(1) } else { None },
+    };
+
+    let (negate, class) = match &pattern[1..end] {
+        [b'^', class @ ..] => (true, class),
+        class => (false, class),
+    };
+
+    let mut found = false;
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == b'-' {
+            found |= class[i] <= b && b <= class[i + 2];
+            i += 3;
+        } else {
+            found |= class[i] == b;
+            i += 1;
+        }
+    }
+
+    if found != negate {
+        Some(end + 1)
+    } else {
+        None
+    }
+}
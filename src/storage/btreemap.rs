@@ -1,6 +1,7 @@
 use super::KvInterface;
 use crate::types::{BvObject, BvString};
 use std::collections::BTreeMap as btmp;
+use std::ops::Bound;
 
 #[derive(Default)]
 pub struct BTreeMap(btmp<BvString, BvObject>);
@@ -49,6 +50,19 @@ impl KvInterface for BTreeMap {
     fn remove(&mut self, key: &Self::RefKey) -> Option<Self::Value> {
         self.0.remove(key)
     }
+
+    fn iter_after(
+        &self,
+        key: Option<&Self::RefKey>,
+    ) -> Box<dyn Iterator<Item = (&Self::Key, &Self::Value)> + '_> {
+        match key {
+            Some(key) => Box::new(
+                self.0
+                    .range::<[u8], _>((Bound::Excluded(key), Bound::Unbounded)),
+            ),
+            None => Box::new(self.0.iter()),
+        }
+    }
 }
 
 impl IntoIterator for BTreeMap {
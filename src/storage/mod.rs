@@ -29,6 +29,14 @@ pub trait KvInterface: std::default::Default + IntoIterator + Import + Export {
     fn get_mut(&mut self, key: &Self::RefKey) -> Option<&mut Self::Value>;
 
     fn remove(&mut self, key: &Self::RefKey) -> Option<Self::Value>;
+
+    /// Records with a key greater than `key` in key order, all records if `key` is None
+    ///
+    /// Used by cursor based scans and index rebuilds.
+    fn iter_after(
+        &self,
+        key: Option<&Self::RefKey>,
+    ) -> Box<dyn Iterator<Item = (&Self::Key, &Self::Value)> + '_>;
 }
 
 pub trait Import {
@@ -0,0 +1,14 @@
+//! Bitmap values, the raw value holds the bits without any length prefix
+//!
+//! Bit offset 0 is the most significant bit of the first byte, see
+//! [ByteVec::get_bit](../bytevec/struct.ByteVec.html#method.get_bit).
+
+use super::BvObject;
+
+/// Type name of bitmap values
+pub const BITMAP_TYPE: &str = "bitmap";
+
+/// Create a bitmap object of raw bytes
+pub fn new_object(bytes: Vec<u8>) -> BvObject {
+    BvObject::from_raw(BITMAP_TYPE.as_bytes().to_vec(), bytes)
+}
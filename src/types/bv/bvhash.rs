@@ -0,0 +1,135 @@
+use std::ops::Range;
+
+use super::elements::{self, bytes_len, object_len};
+use super::BvObject;
+use crate::utils::serialize;
+
+/// Type name of hash values
+pub const HASH_TYPE: &str = "hash";
+
+/// Byte length of a field name and value at the start of v
+fn entry_len(v: &[u8]) -> usize {
+    let f_len = bytes_len(v);
+    f_len + object_len(&v[f_len..])
+}
+
+fn field_of(entry: &[u8]) -> &[u8] {
+    &entry[8..bytes_len(entry)]
+}
+
+fn value_of(entry: &[u8]) -> &[u8] {
+    &entry[bytes_len(entry)..]
+}
+
+/// Hash of field names to BvObjects, edited in place
+///
+/// Fields are kept sorted by name, stored as a bincode serialized Vec<(String, BvObject)>.
+#[derive(Debug)]
+pub struct BvHash<'a> {
+    inner: &'a mut BvObject,
+}
+
+impl<'a> BvHash<'a> {
+    /// Create an empty hash object
+    pub fn new_object() -> BvObject {
+        BvObject::from_raw(HASH_TYPE.as_bytes().to_vec(), elements::empty())
+    }
+
+    pub fn from(obj: &'a mut BvObject) -> Result<Self, String> {
+        if !obj.is_hash() {
+            return Err(format!(
+                "Expected type \"{}\" found type \"{}\"",
+                HASH_TYPE,
+                obj.type_name()
+            ));
+        }
+
+        Ok(BvHash { inner: obj })
+    }
+
+    pub fn len(&self) -> usize {
+        elements::count(self.inner.as_slice())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, field: &str) -> Option<BvObject> {
+        get(self.inner, field)
+    }
+
+    /// Set field to value, returns false if the field already existed
+    pub fn insert(&mut self, field: &str, value: &BvObject) -> bool {
+        let mut entry = serialize(field);
+        entry.extend_from_slice(&elements::encode_object(value));
+
+        match search(self.inner, field) {
+            Ok(r) => {
+                self.inner.splice(r, &entry);
+                false
+            }
+            Err(pos) => {
+                let len = self.len();
+                self.inner.splice(pos..pos, &entry);
+                elements::set_count(self.inner, len + 1);
+                true
+            }
+        }
+    }
+
+    /// Returns false if field don't exists
+    pub fn remove(&mut self, field: &str) -> bool {
+        match search(self.inner, field) {
+            Ok(r) => {
+                let len = self.len();
+                self.inner.splice(r, &[]);
+                elements::set_count(self.inner, len - 1);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+/// Byte range of field, or the position to insert it at
+fn search(obj: &BvObject, field: &str) -> Result<Range<usize>, usize> {
+    let ranges = elements::offsets(obj.as_slice(), entry_len);
+
+    match ranges.binary_search_by(|r| field_of(&obj[r.clone()]).cmp(field.as_bytes())) {
+        Ok(i) => Ok(ranges[i].clone()),
+        Err(i) => Err(ranges.get(i).map(|r| r.start).unwrap_or_else(|| obj.len())),
+    }
+}
+
+pub fn get(obj: &BvObject, field: &str) -> Option<BvObject> {
+    search(obj, field)
+        .ok()
+        .map(|r| elements::decode_object(value_of(&obj[r])))
+}
+
+pub fn contains(obj: &BvObject, field: &str) -> bool {
+    search(obj, field).is_ok()
+}
+
+/// Field names, sorted
+pub fn fields(obj: &BvObject) -> Vec<String> {
+    elements::offsets(obj.as_slice(), entry_len)
+        .into_iter()
+        .map(|r| String::from_utf8_lossy(field_of(&obj[r])).into_owned())
+        .collect()
+}
+
+/// Field names and values, sorted by field name
+pub fn entries(obj: &BvObject) -> Vec<(String, BvObject)> {
+    elements::offsets(obj.as_slice(), entry_len)
+        .into_iter()
+        .map(|r| {
+            let entry = &obj[r];
+            (
+                String::from_utf8_lossy(field_of(entry)).into_owned(),
+                elements::decode_object(value_of(entry)),
+            )
+        })
+        .collect()
+}
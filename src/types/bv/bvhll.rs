@@ -0,0 +1,107 @@
+//! HyperLogLog cardinality estimation
+//!
+//! A HyperLogLog is a fixed array of 2^14 one byte registers(16 KiB), independent of the
+//! number of counted elements. The standard error of the estimated cardinality is
+//! 1.04 / sqrt(2^14) ≈ 0.81%, i.e about 68% of estimates are within 0.81% and about 95%
+//! within 1.63% of the true cardinality.
+//!
+//! Elements are hashed with a stable 64 bit hash, so registers can be persisted and
+//! merged across processes.
+
+use super::{BvObject, ByteVec};
+
+/// Type name of HyperLogLog values
+pub const HLL_TYPE: &str = "hll";
+
+/// Number of index bits
+const P: u32 = 14;
+
+/// Number of registers
+pub const REGISTERS: usize = 1 << P;
+
+/// FNV-1a, finalized with the MurmurHash3 mix for a better bit distribution
+fn hash(v: &[u8]) -> u64 {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in v {
+        h ^= *b as u64;
+        h = h.wrapping_mul(0x0100_0000_01b3);
+    }
+
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    h ^ (h >> 33)
+}
+
+/// HyperLogLog registers of a ByteVec, edited in place
+#[derive(Debug)]
+pub struct BvHll<'a> {
+    inner: &'a mut ByteVec,
+}
+
+impl<'a> BvHll<'a> {
+    /// Create empty registers
+    pub fn new_bytevec() -> ByteVec {
+        ByteVec::from_vec(vec![0; REGISTERS])
+    }
+
+    /// Create an empty HyperLogLog object
+    pub fn new_object() -> BvObject {
+        BvObject::from_raw(HLL_TYPE.as_bytes().to_vec(), vec![0; REGISTERS])
+    }
+
+    pub fn from(v: &'a mut ByteVec) -> Result<Self, String> {
+        if v.len() != REGISTERS {
+            return Err(format!(
+                "Expected {} HyperLogLog registers found {} bytes",
+                REGISTERS,
+                v.len()
+            ));
+        }
+
+        Ok(BvHll { inner: v })
+    }
+
+    /// Count element, returns true if a register was updated
+    pub fn add(&mut self, element: &[u8]) -> bool {
+        let h = hash(element);
+        let index = (h >> (64 - P)) as usize;
+        let rank = ((h << P).leading_zeros().min(64 - P) + 1) as u8;
+
+        if rank > self.inner[index] {
+            self.inner[index] = rank;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Merge the registers of other into self, the result estimates the union of both
+    pub fn merge(&mut self, other: &[u8]) {
+        for (i, o) in other.iter().enumerate().take(REGISTERS) {
+            self.inner[i] = self.inner[i].max(*o);
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        count(self.inner.as_slice())
+    }
+}
+
+/// Estimated cardinality of registers
+pub fn count(registers: &[u8]) -> u64 {
+    let m = REGISTERS as f64;
+    let alpha = 0.7213 / (1.0 + 1.079 / m);
+
+    let sum: f64 = registers.iter().map(|r| 2f64.powi(-(*r as i32))).sum();
+    let estimate = alpha * m * m / sum;
+
+    let zeros = registers.iter().filter(|r| **r == 0).count();
+    if estimate <= 2.5 * m && zeros > 0 {
+        // Linear counting for small cardinalities
+        (m * (m / zeros as f64).ln()).round() as u64
+    } else {
+        estimate.round() as u64
+    }
+}
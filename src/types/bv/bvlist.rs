@@ -0,0 +1,141 @@
+use super::elements::{self, object_len};
+use super::BvObject;
+
+/// Type name of list values
+pub const LIST_TYPE: &str = "list";
+
+/// List of BvObjects, edited in place
+///
+/// Stored as a bincode serialized Vec<BvObject>, i.e `extract::<Vec<BvObject>>()` works on list values.
+#[derive(Debug)]
+pub struct BvList<'a> {
+    inner: &'a mut BvObject,
+}
+
+impl<'a> BvList<'a> {
+    /// Create an empty list object
+    pub fn new_object() -> BvObject {
+        BvObject::from_raw(LIST_TYPE.as_bytes().to_vec(), elements::empty())
+    }
+
+    pub fn from(obj: &'a mut BvObject) -> Result<Self, String> {
+        if !obj.is_list() {
+            return Err(format!(
+                "Expected type \"{}\" found type \"{}\"",
+                LIST_TYPE,
+                obj.type_name()
+            ));
+        }
+
+        Ok(BvList { inner: obj })
+    }
+
+    pub fn len(&self) -> usize {
+        BvList::len_of(self.inner)
+    }
+
+    /// Length of a list object
+    pub fn len_of(obj: &BvObject) -> usize {
+        elements::count(obj.as_slice())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: isize) -> Option<BvObject> {
+        get(self.inner, index)
+    }
+
+    pub fn range(&self, start: isize, stop: isize) -> Vec<BvObject> {
+        range(self.inner, start, stop)
+    }
+
+    pub fn push_front(&mut self, value: &BvObject) {
+        let len = self.len();
+        self.inner.splice(
+            elements::COUNT_BS..elements::COUNT_BS,
+            &elements::encode_object(value),
+        );
+        elements::set_count(self.inner, len + 1);
+    }
+
+    pub fn push_back(&mut self, value: &BvObject) {
+        let len = self.len();
+        self.inner
+            .extend_from_slice(&elements::encode_object(value));
+        elements::set_count(self.inner, len + 1);
+    }
+
+    pub fn pop_front(&mut self) -> Option<BvObject> {
+        self.remove(0)
+    }
+
+    pub fn pop_back(&mut self) -> Option<BvObject> {
+        self.remove(-1)
+    }
+
+    pub fn remove(&mut self, index: isize) -> Option<BvObject> {
+        let len = self.len();
+        let index = elements::normalize_index(index, len)?;
+        let r = elements::offsets(self.inner.as_slice(), object_len)[index].clone();
+        let value = elements::decode_object(&self.inner[r.clone()]);
+
+        self.inner.splice(r, &[]);
+        elements::set_count(self.inner, len - 1);
+
+        Some(value)
+    }
+
+    /// Overwrite the element at index, the new element may differ in type and length
+    pub fn set(&mut self, index: isize, value: &BvObject) -> Result<(), String> {
+        let len = self.len();
+        let index = elements::normalize_index(index, len)
+            .ok_or_else(|| format!("Index {} out of range, length {}", index, len))?;
+        let r = elements::offsets(self.inner.as_slice(), object_len)[index].clone();
+
+        self.inner.splice(r, &elements::encode_object(value));
+        Ok(())
+    }
+
+    /// Keep only the elements within the inclusive range
+    pub fn trim(&mut self, start: isize, stop: isize) {
+        let ranges = elements::offsets(self.inner.as_slice(), object_len);
+
+        let total = self.inner.len();
+
+        match elements::normalize_range(start, stop, ranges.len()) {
+            Some(keep) => {
+                // Cut the tail first so the head offsets stay valid
+                self.inner.splice(ranges[keep.end - 1].end..total, &[]);
+                self.inner
+                    .splice(elements::COUNT_BS..ranges[keep.start].start, &[]);
+                elements::set_count(self.inner, keep.len());
+            }
+            None => {
+                self.inner.splice(elements::COUNT_BS..total, &[]);
+                elements::set_count(self.inner, 0);
+            }
+        }
+    }
+}
+
+/// Element at index of a list object, negative indexes count from the end
+pub fn get(obj: &BvObject, index: isize) -> Option<BvObject> {
+    let ranges = elements::offsets(obj.as_slice(), object_len);
+    let index = elements::normalize_index(index, ranges.len())?;
+    Some(elements::decode_object(&obj[ranges[index].clone()]))
+}
+
+/// Elements within the inclusive range of a list object
+pub fn range(obj: &BvObject, start: isize, stop: isize) -> Vec<BvObject> {
+    let ranges = elements::offsets(obj.as_slice(), object_len);
+
+    match elements::normalize_range(start, stop, ranges.len()) {
+        Some(r) => ranges[r]
+            .iter()
+            .map(|r| elements::decode_object(&obj[r.clone()]))
+            .collect(),
+        None => Vec::new(),
+    }
+}
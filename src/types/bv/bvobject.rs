@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 use crate::prelude::{BvContains, BvEndsWith, BvStartsWith};
 use crate::utils::{normalize_type_name, serialize_to_bytevec};
 
+use super::numeric::{self, NumOp, Overflow};
 use super::{BvString, ByteVec};
 
 /// Wrapper for serialized objects by bincode
@@ -54,6 +55,34 @@ impl BvObject {
         self.type_name == "str"
     }
 
+    pub fn is_list(&self) -> bool {
+        self.type_name == super::bvlist::LIST_TYPE
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.type_name == super::bvset::SET_TYPE
+    }
+
+    pub fn is_bitmap(&self) -> bool {
+        self.type_name == super::bvbitmap::BITMAP_TYPE
+    }
+
+    pub fn is_hll(&self) -> bool {
+        self.type_name == super::bvhll::HLL_TYPE
+    }
+
+    pub fn is_hash(&self) -> bool {
+        self.type_name == super::bvhash::HASH_TYPE
+    }
+
+    pub fn is_stream(&self) -> bool {
+        self.type_name == super::bvstream::STREAM_TYPE
+    }
+
+    pub fn is_zset(&self) -> bool {
+        self.type_name == super::bvzset::ZSET_TYPE
+    }
+
     pub fn is_int(&self) -> bool {
         match self.type_name.as_slice() {
             // i8-i128
@@ -98,6 +127,63 @@ impl BvObject {
     pub fn as_str_slice(&self) -> &[u8] {
         &self.raw.as_slice()[8..]
     }
+
+    /// Integer or float
+    pub fn is_numeric(&self) -> bool {
+        numeric::is_numeric(self)
+    }
+
+    /// Add rhs, Err on overflow
+    ///
+    /// Err if either value isn't numeric, see [numeric](../numeric/index.html) for type promotion.
+    pub fn checked_add(&self, rhs: &BvObject) -> Result<BvObject, String> {
+        numeric::calc(self, NumOp::Add, rhs, Overflow::Checked)
+    }
+
+    /// Subtract rhs, Err on overflow
+    pub fn checked_sub(&self, rhs: &BvObject) -> Result<BvObject, String> {
+        numeric::calc(self, NumOp::Sub, rhs, Overflow::Checked)
+    }
+
+    /// Multiply by rhs, Err on overflow
+    pub fn checked_mul(&self, rhs: &BvObject) -> Result<BvObject, String> {
+        numeric::calc(self, NumOp::Mul, rhs, Overflow::Checked)
+    }
+
+    /// Divide by rhs, Err on overflow or division by zero
+    pub fn checked_div(&self, rhs: &BvObject) -> Result<BvObject, String> {
+        numeric::calc(self, NumOp::Div, rhs, Overflow::Checked)
+    }
+
+    /// Add rhs, clamped to the bounds of the result type
+    pub fn saturating_add(&self, rhs: &BvObject) -> Result<BvObject, String> {
+        numeric::calc(self, NumOp::Add, rhs, Overflow::Saturating)
+    }
+
+    /// Subtract rhs, clamped to the bounds of the result type
+    pub fn saturating_sub(&self, rhs: &BvObject) -> Result<BvObject, String> {
+        numeric::calc(self, NumOp::Sub, rhs, Overflow::Saturating)
+    }
+
+    /// Multiply by rhs, clamped to the bounds of the result type
+    pub fn saturating_mul(&self, rhs: &BvObject) -> Result<BvObject, String> {
+        numeric::calc(self, NumOp::Mul, rhs, Overflow::Saturating)
+    }
+
+    /// Divide by rhs, clamped to the bounds of the result type, Err on division by zero
+    pub fn saturating_div(&self, rhs: &BvObject) -> Result<BvObject, String> {
+        numeric::calc(self, NumOp::Div, rhs, Overflow::Saturating)
+    }
+
+    /// Numerically smaller of self and rhs, in the promoted type
+    pub fn num_min(&self, rhs: &BvObject) -> Result<BvObject, String> {
+        numeric::calc(self, NumOp::Min, rhs, Overflow::Checked)
+    }
+
+    /// Numerically larger of self and rhs, in the promoted type
+    pub fn num_max(&self, rhs: &BvObject) -> Result<BvObject, String> {
+        numeric::calc(self, NumOp::Max, rhs, Overflow::Checked)
+    }
 }
 
 impl std::ops::Deref for BvObject {
@@ -0,0 +1,113 @@
+use super::elements::{self, object_len};
+use super::BvObject;
+
+/// Type name of set values
+pub const SET_TYPE: &str = "set";
+
+/// Set of BvObjects, edited in place
+///
+/// Members are compared by their serialized bytes(type name and value) and kept sorted,
+/// stored as a bincode serialized Vec<BvObject>.
+#[derive(Debug)]
+pub struct BvSet<'a> {
+    inner: &'a mut BvObject,
+}
+
+impl<'a> BvSet<'a> {
+    /// Create an empty set object
+    pub fn new_object() -> BvObject {
+        BvObject::from_raw(SET_TYPE.as_bytes().to_vec(), elements::empty())
+    }
+
+    /// Create a set object of serialized members, members must be sorted and unique
+    pub fn from_encoded(members: &[Vec<u8>]) -> BvObject {
+        let mut raw = elements::empty();
+        raw[..elements::COUNT_BS].copy_from_slice(&(members.len() as u64).to_le_bytes());
+        for member in members {
+            raw.extend_from_slice(member);
+        }
+
+        BvObject::from_raw(SET_TYPE.as_bytes().to_vec(), raw)
+    }
+
+    pub fn from(obj: &'a mut BvObject) -> Result<Self, String> {
+        if !obj.is_set() {
+            return Err(format!(
+                "Expected type \"{}\" found type \"{}\"",
+                SET_TYPE,
+                obj.type_name()
+            ));
+        }
+
+        Ok(BvSet { inner: obj })
+    }
+
+    pub fn len(&self) -> usize {
+        elements::count(self.inner.as_slice())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, member: &BvObject) -> bool {
+        contains(self.inner, member)
+    }
+
+    /// Returns false if member already exists
+    pub fn insert(&mut self, member: &BvObject) -> bool {
+        let encoded = elements::encode_object(member);
+        let ranges = elements::offsets(self.inner.as_slice(), object_len);
+
+        match ranges.binary_search_by(|r| self.inner[r.clone()].cmp(&encoded)) {
+            Ok(_) => false,
+            Err(i) => {
+                let pos = match ranges.get(i) {
+                    Some(r) => r.start,
+                    None => self.inner.len(),
+                };
+
+                self.inner.splice(pos..pos, &encoded);
+                elements::set_count(self.inner, ranges.len() + 1);
+                true
+            }
+        }
+    }
+
+    /// Returns false if member don't exists
+    pub fn remove(&mut self, member: &BvObject) -> bool {
+        let encoded = elements::encode_object(member);
+        let ranges = elements::offsets(self.inner.as_slice(), object_len);
+
+        match ranges.binary_search_by(|r| self.inner[r.clone()].cmp(&encoded)) {
+            Ok(i) => {
+                self.inner.splice(ranges[i].clone(), &[]);
+                elements::set_count(self.inner, ranges.len() - 1);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+pub fn contains(obj: &BvObject, member: &BvObject) -> bool {
+    let encoded = elements::encode_object(member);
+    elements::offsets(obj.as_slice(), object_len)
+        .binary_search_by(|r| obj[r.clone()].cmp(&encoded))
+        .is_ok()
+}
+
+/// Serialized members of a set object, sorted
+pub fn encoded_members(obj: &BvObject) -> Vec<Vec<u8>> {
+    elements::offsets(obj.as_slice(), object_len)
+        .into_iter()
+        .map(|r| obj[r].to_vec())
+        .collect()
+}
+
+pub fn members(obj: &BvObject) -> Vec<BvObject> {
+    elements::offsets(obj.as_slice(), object_len)
+        .into_iter()
+        .map(|r| elements::decode_object(&obj[r]))
+        .collect()
+}
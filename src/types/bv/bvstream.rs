@@ -0,0 +1,254 @@
+use std::convert::TryFrom;
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use super::elements::{self, bytes_len, object_len};
+use super::{BvObject, ByteVec};
+use crate::utils::{deserialize, serialize};
+
+/// Type name of stream values
+pub const STREAM_TYPE: &str = "stream";
+
+const ID_BS: usize = 16;
+
+/// ID of a stream entry, milliseconds since the unix epoch and a sequence number
+/// for entries added within the same millisecond
+///
+/// Formatted and parsed as "<ms>-<seq>".
+#[derive(
+    Default, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+pub struct StreamId {
+    pub ms: u64,
+    pub seq: u64,
+}
+
+impl StreamId {
+    pub const MIN: StreamId = StreamId { ms: 0, seq: 0 };
+    pub const MAX: StreamId = StreamId {
+        ms: u64::MAX,
+        seq: u64::MAX,
+    };
+
+    pub fn new(ms: u64, seq: u64) -> Self {
+        StreamId { ms, seq }
+    }
+
+    /// The ID following self
+    pub fn next(&self) -> Option<StreamId> {
+        match self.seq.checked_add(1) {
+            Some(seq) => Some(StreamId::new(self.ms, seq)),
+            None => Some(StreamId::new(self.ms.checked_add(1)?, 0)),
+        }
+    }
+
+    /// The next ID after self for an entry added at `now` milliseconds
+    pub fn next_at(&self, now: u64) -> Option<StreamId> {
+        if now > self.ms {
+            Some(StreamId::new(now, 0))
+        } else {
+            self.next()
+        }
+    }
+
+    fn to_bytes(self) -> [u8; ID_BS] {
+        let mut b = [0; ID_BS];
+        b[..8].copy_from_slice(&self.ms.to_le_bytes());
+        b[8..].copy_from_slice(&self.seq.to_le_bytes());
+        b
+    }
+
+    fn from_bytes(v: &[u8]) -> Self {
+        StreamId::new(
+            u64::from_le_bytes(<[u8; 8]>::try_from(&v[..8]).unwrap()),
+            u64::from_le_bytes(<[u8; 8]>::try_from(&v[8..ID_BS]).unwrap()),
+        )
+    }
+}
+
+impl std::fmt::Display for StreamId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+impl std::str::FromStr for StreamId {
+    type Err = String;
+
+    /// Parse "<ms>-<seq>" or "<ms>", the sequence number defaults to 0
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid stream ID \"{}\"", s);
+        let mut parts = s.splitn(2, '-');
+
+        let ms = parts.next().unwrap().parse().map_err(|_| invalid())?;
+        let seq = match parts.next() {
+            Some(seq) => seq.parse().map_err(|_| invalid())?,
+            None => 0,
+        };
+
+        Ok(StreamId::new(ms, seq))
+    }
+}
+
+/// A named consumer group, tracking the last entry delivered to it and the
+/// delivered entries not yet acknowledged
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StreamGroup {
+    pub name: String,
+    pub last_delivered: StreamId,
+    pub pending: Vec<StreamId>,
+}
+
+/// Byte length of an entry ID and value at the start of v
+fn entry_len(v: &[u8]) -> usize {
+    ID_BS + object_len(&v[ID_BS..])
+}
+
+/// Append only log of BvObjects ordered by their IDs, edited in place
+///
+/// Stored as the last generated ID, the serialized consumer groups and a bincode
+/// serialized Vec<(StreamId, BvObject)>. Entries are appended to the end of the value.
+#[derive(Debug)]
+pub struct BvStream<'a> {
+    inner: &'a mut BvObject,
+}
+
+impl<'a> BvStream<'a> {
+    /// Create an empty stream object
+    pub fn new_object() -> BvObject {
+        let mut raw = StreamId::MIN.to_bytes().to_vec();
+        raw.extend_from_slice(&serialize(&serialize(&Vec::<StreamGroup>::new())));
+        raw.extend_from_slice(&elements::empty());
+
+        BvObject::from_raw(STREAM_TYPE.as_bytes().to_vec(), raw)
+    }
+
+    pub fn from(obj: &'a mut BvObject) -> Result<Self, String> {
+        if !obj.is_stream() {
+            return Err(format!(
+                "Expected type \"{}\" found type \"{}\"",
+                STREAM_TYPE,
+                obj.type_name()
+            ));
+        }
+
+        Ok(BvStream { inner: obj })
+    }
+
+    pub fn len(&self) -> usize {
+        len(self.inner)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Append value with the next ID for the time `now` in milliseconds
+    pub fn push(&mut self, now: u64, value: &BvObject) -> Result<StreamId, String> {
+        let id = last_id(self.inner)
+            .next_at(now)
+            .ok_or_else(|| "Stream IDs exhausted".to_string())?;
+
+        let start = entries_start(self.inner);
+        let len = self.len();
+
+        let mut entry = id.to_bytes().to_vec();
+        entry.extend_from_slice(&elements::encode_object(value));
+        self.inner.extend_from_slice(&entry);
+        set_count(self.inner, start, len + 1);
+        self.inner[..ID_BS].copy_from_slice(&id.to_bytes());
+
+        Ok(id)
+    }
+
+    /// Remove the oldest entries until at most `max_len` remains, returns the number of removed entries
+    pub fn trim(&mut self, max_len: usize) -> usize {
+        let len = self.len();
+        if len <= max_len {
+            return 0;
+        }
+
+        let ranges = entry_ranges(self.inner);
+        let removed = len - max_len;
+        // Everything up to the end when no entry is kept
+        let end = match ranges.get(removed) {
+            Some(r) => r.start,
+            None => self.inner.len(),
+        };
+        self.inner.splice(ranges[0].start..end, &[]);
+        let start = entries_start(self.inner);
+        set_count(self.inner, start, max_len);
+
+        removed
+    }
+
+    pub fn set_groups(&mut self, groups: &[StreamGroup]) {
+        let r = groups_range(self.inner);
+        self.inner.splice(r, &serialize(&serialize(groups)));
+    }
+}
+
+fn groups_range(obj: &ByteVec) -> Range<usize> {
+    ID_BS..ID_BS + bytes_len(&obj[ID_BS..])
+}
+
+fn entries_start(obj: &ByteVec) -> usize {
+    groups_range(obj).end
+}
+
+fn set_count(obj: &mut ByteVec, start: usize, count: usize) {
+    obj[start..start + elements::COUNT_BS].copy_from_slice(&(count as u64).to_le_bytes());
+}
+
+/// Byte ranges of all entries
+fn entry_ranges(obj: &ByteVec) -> Vec<Range<usize>> {
+    let start = entries_start(obj);
+    elements::offsets(&obj[start..], entry_len)
+        .into_iter()
+        .map(|r| r.start + start..r.end + start)
+        .collect()
+}
+
+fn decode_entry(v: &[u8]) -> (StreamId, BvObject) {
+    (
+        StreamId::from_bytes(v),
+        elements::decode_object(&v[ID_BS..]),
+    )
+}
+
+pub fn len(obj: &BvObject) -> usize {
+    elements::count(&obj[entries_start(obj)..])
+}
+
+/// The last generated ID, trimmed entries included
+pub fn last_id(obj: &BvObject) -> StreamId {
+    StreamId::from_bytes(obj.as_slice())
+}
+
+pub fn groups(obj: &BvObject) -> Vec<StreamGroup> {
+    let r = groups_range(obj);
+    deserialize(&obj[r.start + 8..r.end])
+}
+
+/// Entries with start <= ID <= end, ordered by ID
+pub fn range(obj: &BvObject, start: StreamId, end: StreamId) -> Vec<(StreamId, BvObject)> {
+    entry_ranges(obj)
+        .into_iter()
+        .map(|r| &obj[r])
+        .skip_while(|entry| StreamId::from_bytes(entry) < start)
+        .take_while(|entry| StreamId::from_bytes(entry) <= end)
+        .map(decode_entry)
+        .collect()
+}
+
+/// At most `count` entries with an ID greater than `after`, ordered by ID
+pub fn after(obj: &BvObject, after: StreamId, count: usize) -> Vec<(StreamId, BvObject)> {
+    entry_ranges(obj)
+        .into_iter()
+        .map(|r| &obj[r])
+        .skip_while(|entry| StreamId::from_bytes(entry) <= after)
+        .take(count)
+        .map(decode_entry)
+        .collect()
+}
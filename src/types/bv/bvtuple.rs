@@ -1,105 +1,213 @@
+use super::layout::{self, Layout};
+use super::numeric::{self, NumOp, Overflow};
 use super::{BvObj, BvObject, BvString};
 use crate::normalize_type_name;
-use crate::slice;
-use crate::utils::{deserialize, is_int, serialize_object};
+use crate::utils::serialize_object;
 
+/// In-place access to the elements of a tuple
+///
+/// Elements can be of any type supported by [Layout](../layout/enum.Layout.html). Elements of
+/// variable length, strings, Options and Vecs, can be replaced by values of another length,
+/// the underlying BvObject is resized.
 #[derive(Debug)]
 pub struct BvTuple<'a> {
-    inner: &'a mut BvObject,
+    inner: Inner<'a>,
     elength: Vec<usize>,
+    fixed: Vec<bool>,
     type_map: Vec<BvString>,
 }
 
+#[derive(Debug)]
+enum Inner<'a> {
+    Mut(&'a mut BvObject),
+    Shared(&'a BvObject),
+}
+
 impl<'a> BvTuple<'a> {
-    pub fn from(obj: &'a mut BvObject) -> Self {
-        // TODO
-        // Optimize away, calculate element len and JIT type name check instead of vec alloc
-        let type_map = slice::split(&obj.type_name()[1..obj.type_name().len() - 1], b", ")
+    /// Err if obj isn't a tuple or has elements of unsupported types, like structs and enums
+    pub fn from(obj: &'a mut BvObject) -> Result<Self, String> {
+        BvTuple::new(Inner::Mut(obj))
+    }
+
+    /// Tuple whose elements can't be changed, set and incr_elem/decr_elem return Err
+    ///
+    pub fn read_only(obj: &'a BvObject) -> Result<Self, String> {
+        BvTuple::new(Inner::Shared(obj))
+    }
+
+    fn new(inner: Inner<'a>) -> Result<Self, String> {
+        let obj: &BvObject = match &inner {
+            Inner::Mut(obj) => obj,
+            Inner::Shared(obj) => obj,
+        };
+
+        let type_name = obj.type_name().as_slice();
+        if type_name.len() < 2 || type_name[0] != b'(' || type_name[type_name.len() - 1] != b')' {
+            return Err(format!(
+                "Expected a tuple found type \"{}\"",
+                obj.type_name()
+            ));
+        }
+
+        let elements = layout::split_elements(
+            std::str::from_utf8(&type_name[1..type_name.len() - 1]).unwrap(),
+        );
+        let type_map = elements
             .iter()
-            .map(|r| BvString::from(normalize_type_name(r).to_vec()))
+            .map(|r| BvString::from(normalize_type_name(r.as_bytes()).to_vec()))
             .collect::<Vec<_>>();
 
         let mut elength = vec![];
-        for r#type in type_map.iter() {
-            let r = match r#type.as_slice() {
-                // i8-i128
-                [105, 56] => 1,
-                [105, 49, 54] => 2,
-                [105, 51, 50] => 4,
-                [105, 54, 52] => 8,
-                [105, 49, 50, 56] => 16,
-                // u8-u128
-                [117, 56] => 1,
-                [117, 49, 54] => 2,
-                [117, 51, 50] => 4,
-                [117, 54, 52] => 8,
-                [117, 49, 50, 56] => 16,
-                // f32-f64
-                [102, 51, 50] => 4,
-                [102, 54, 52] => 8,
-                // Str
-                [115, 116, 114] => {
-                    // Length of str serialized by bincode, usize(str lengt) + str length
-                    let curr_pos = elength.iter().sum();
-                    8 + deserialize::<usize>(&obj[curr_pos..curr_pos + 8])
-                }
-                _ => panic!("Failed to calculate tuple size"),
-            };
-
-            elength.push(r);
+        let mut fixed = vec![];
+        let mut pos = 0;
+        for element in elements {
+            // Parse the raw name, f32 is normalized to f64 but serialized in 4 bytes
+            let layout = Layout::parse(element)?;
+            let len = layout.encoded_len(&obj[pos..])?;
+            elength.push(len);
+            fixed.push(layout.is_fixed());
+            pos += len;
         }
 
-        BvTuple {
-            inner: obj,
+        Ok(BvTuple {
+            inner,
             elength,
+            fixed,
             type_map,
+        })
+    }
+
+    fn inner(&self) -> &BvObject {
+        match &self.inner {
+            Inner::Mut(obj) => obj,
+            Inner::Shared(obj) => obj,
         }
     }
 
-    pub fn get_start(&self, index: usize) -> usize {
-        let mut start = 0;
-        for i in 0..index {
-            start += self.elength[i];
+    fn inner_mut(&mut self) -> Result<&mut BvObject, String> {
+        match &mut self.inner {
+            Inner::Mut(obj) => Ok(obj),
+            Inner::Shared(_) => Err("Tuple is read-only, use KvDb::update_tuple".to_string()),
         }
+    }
 
-        start
+    pub fn len(&self) -> usize {
+        self.type_map.len()
     }
 
-    pub fn get(&'a self, index: usize) -> BvObj<'a> {
-        let r = &self.inner[self.get_start(index)..self.get_start(index) + self.elength[index]];
+    pub fn is_empty(&self) -> bool {
+        self.type_map.is_empty()
+    }
 
-        if self.type_map[index] == "str" || is_int(self.type_map[index].as_slice()) {
-            BvObj::new(self.type_map[index].as_slice(), r)
-        } else {
-            panic!("Failed to implicit cast byte slice to type")
-        }
+    pub fn get_start(&self, index: usize) -> usize {
+        self.elength[..index].iter().sum()
     }
 
-    pub fn value<T: serde::de::DeserializeOwned>(&self, index: usize) -> T {
+    pub fn get(&self, index: usize) -> Result<BvObj<'_>, String> {
+        self.check_index(index)?;
+        let r = &self.inner()[self.get_start(index)..self.get_start(index) + self.elength[index]];
+
+        Ok(BvObj::new(self.type_map[index].as_slice(), r))
+    }
+
+    pub fn value<T: serde::de::DeserializeOwned>(&self, index: usize) -> Result<T, String> {
+        self.check_index(index)?;
         let start = self.get_start(index);
-        deserialize(&self.inner[start..start + self.elength[index]])
+        bincode::deserialize(&self.inner()[start..start + self.elength[index]])
+            .map_err(|e| e.to_string())
     }
 
-    pub fn set<T: Sized + serde::Serialize>(&mut self, index: usize, value: T) {
+    /// Replace an element with a value of the same type, variable length elements may differ in length
+    ///
+    pub fn set<T: Sized + serde::Serialize>(
+        &mut self,
+        index: usize,
+        value: T,
+    ) -> Result<(), String> {
+        self.check_index(index)?;
         let new_value = serialize_object(&value);
         if self.type_map[index] != new_value.type_name() {
-            panic!("Not the same type")
+            return Err(format!(
+                "Expected type \"{}\" found type \"{}\"",
+                self.type_map[index],
+                new_value.type_name()
+            ));
         }
 
         let length = self.elength[index];
-        if length != new_value.len() {
-            panic!(
+        if length != new_value.len() && self.fixed[index] {
+            return Err(format!(
                 "Not the same length, overwriting len {} with len {}",
                 length,
                 new_value.len()
-            )
+            ));
         }
 
         let start = self.get_start(index);
-        let new_slice = new_value.as_slice();
+        self.inner_mut()?
+            .splice(start..start + length, new_value.as_slice());
+        self.elength[index] = new_value.len();
 
-        for (x, i) in (start..start + length).enumerate() {
-            self.inner[i] = new_slice[x];
+        Ok(())
+    }
+
+    /// Add by to a numeric element and return the new element, Err on overflow
+    ///
+    /// See [numeric](../numeric/index.html) for type promotion, the element keeps its type.
+    pub fn incr_elem<T: serde::Serialize>(
+        &mut self,
+        index: usize,
+        by: T,
+    ) -> Result<BvObject, String> {
+        self.calc_elem(index, NumOp::Add, by)
+    }
+
+    /// Subtract by from a numeric element and return the new element, Err on overflow
+    ///
+    pub fn decr_elem<T: serde::Serialize>(
+        &mut self,
+        index: usize,
+        by: T,
+    ) -> Result<BvObject, String> {
+        self.calc_elem(index, NumOp::Sub, by)
+    }
+
+    fn calc_elem<T: serde::Serialize>(
+        &mut self,
+        index: usize,
+        op: NumOp,
+        operand: T,
+    ) -> Result<BvObject, String> {
+        self.check_index(index)?;
+        let start = self.get_start(index);
+        let elem = BvObject::from_raw(
+            self.type_map[index].as_slice().to_vec(),
+            self.inner()[start..start + self.elength[index]].to_vec(),
+        );
+
+        let new_elem = numeric::calc(&elem, op, &serialize_object(&operand), Overflow::Checked)?;
+        if new_elem.type_name() != elem.type_name() || new_elem.len() != elem.len() {
+            return Err(format!(
+                "Result of type \"{}\" doesn't fit element of type \"{}\"",
+                new_elem.type_name(),
+                elem.type_name()
+            ));
+        }
+
+        self.inner_mut()?
+            .splice(start..start + elem.len(), new_elem.as_slice());
+        Ok(new_elem)
+    }
+
+    fn check_index(&self, index: usize) -> Result<(), String> {
+        if index < self.len() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Index {} out of range for tuple of length {}",
+                index,
+                self.len()
+            ))
         }
     }
 }
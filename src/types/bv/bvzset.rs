@@ -0,0 +1,147 @@
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::ops::Range;
+
+use super::elements::{self, object_len};
+use super::BvObject;
+
+/// Type name of sorted set values
+pub const ZSET_TYPE: &str = "zset";
+
+/// Byte length of a score and member at the start of v
+fn entry_len(v: &[u8]) -> usize {
+    8 + object_len(&v[8..])
+}
+
+fn score_of(v: &[u8]) -> f64 {
+    f64::from_le_bytes(<[u8; 8]>::try_from(&v[..8]).unwrap())
+}
+
+fn encode_entry(score: f64, member: &[u8]) -> Vec<u8> {
+    let mut entry = score.to_le_bytes().to_vec();
+    entry.extend_from_slice(member);
+    entry
+}
+
+/// Order by score, then by serialized member bytes
+fn cmp_entry(a: &[u8], b: &[u8]) -> Ordering {
+    score_of(a)
+        .partial_cmp(&score_of(b))
+        .unwrap_or(Ordering::Equal)
+        .then_with(|| a[8..].cmp(&b[8..]))
+}
+
+/// Sorted set of BvObjects ordered by an f64 score, edited in place
+///
+/// Members with equal scores are ordered by their serialized bytes. Stored as a
+/// bincode serialized Vec<(f64, BvObject)>.
+#[derive(Debug)]
+pub struct BvZSet<'a> {
+    inner: &'a mut BvObject,
+}
+
+impl<'a> BvZSet<'a> {
+    /// Create an empty sorted set object
+    pub fn new_object() -> BvObject {
+        BvObject::from_raw(ZSET_TYPE.as_bytes().to_vec(), elements::empty())
+    }
+
+    pub fn from(obj: &'a mut BvObject) -> Result<Self, String> {
+        if !obj.is_zset() {
+            return Err(format!(
+                "Expected type \"{}\" found type \"{}\"",
+                ZSET_TYPE,
+                obj.type_name()
+            ));
+        }
+
+        Ok(BvZSet { inner: obj })
+    }
+
+    pub fn len(&self) -> usize {
+        elements::count(self.inner.as_slice())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Insert member or update its score, returns false if the member already existed
+    pub fn insert(&mut self, member: &BvObject, score: f64) -> bool {
+        let existed = self.remove(member).is_some();
+        let entry = encode_entry(score, &elements::encode_object(member));
+        let ranges = elements::offsets(self.inner.as_slice(), entry_len);
+
+        let i = ranges
+            .binary_search_by(|r| cmp_entry(&self.inner[r.clone()], &entry))
+            .unwrap_or_else(|i| i);
+        let pos = match ranges.get(i) {
+            Some(r) => r.start,
+            None => self.inner.len(),
+        };
+
+        self.inner.splice(pos..pos, &entry);
+        elements::set_count(self.inner, ranges.len() + 1);
+
+        !existed
+    }
+
+    /// Remove member and return its score
+    pub fn remove(&mut self, member: &BvObject) -> Option<f64> {
+        let (_, r) = find(self.inner, member)?;
+        let score = score_of(&self.inner[r.clone()]);
+        let len = self.len();
+
+        self.inner.splice(r, &[]);
+        elements::set_count(self.inner, len - 1);
+
+        Some(score)
+    }
+}
+
+/// Index and byte range of member
+fn find(obj: &BvObject, member: &BvObject) -> Option<(usize, Range<usize>)> {
+    let encoded = elements::encode_object(member);
+    elements::offsets(obj.as_slice(), entry_len)
+        .into_iter()
+        .enumerate()
+        .find(|(_, r)| obj[r.start + 8..r.end] == encoded[..])
+}
+
+fn decode_entry(v: &[u8]) -> (BvObject, f64) {
+    (elements::decode_object(&v[8..]), score_of(v))
+}
+
+/// Score of member
+pub fn score(obj: &BvObject, member: &BvObject) -> Option<f64> {
+    find(obj, member).map(|(_, r)| score_of(&obj[r]))
+}
+
+/// Position of member, ordered by ascending score
+pub fn rank(obj: &BvObject, member: &BvObject) -> Option<usize> {
+    find(obj, member).map(|(i, _)| i)
+}
+
+/// Members and scores by position within the inclusive range, ordered by ascending score
+pub fn range(obj: &BvObject, start: isize, stop: isize) -> Vec<(BvObject, f64)> {
+    let ranges = elements::offsets(obj.as_slice(), entry_len);
+
+    match elements::normalize_range(start, stop, ranges.len()) {
+        Some(r) => ranges[r]
+            .iter()
+            .map(|r| decode_entry(&obj[r.clone()]))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Members and scores with min <= score <= max, ordered by ascending score
+pub fn range_by_score(obj: &BvObject, min: f64, max: f64) -> Vec<(BvObject, f64)> {
+    elements::offsets(obj.as_slice(), entry_len)
+        .into_iter()
+        .map(|r| &obj[r])
+        .skip_while(|entry| score_of(entry) < min)
+        .take_while(|entry| score_of(entry) <= max)
+        .map(decode_entry)
+        .collect()
+}
@@ -86,7 +86,12 @@ impl<'a> ByteSlice<'a> {
         f32::from_le_bytes(<[u8; 4]>::try_from(&self[..4]).unwrap())
     }
 
+    /// f32 values, which share the type name f64, are widened
     pub fn as_f64(&self) -> f64 {
+        if self.len() == 4 {
+            return self.as_f32() as f64;
+        }
+
         f64::from_le_bytes(<[u8; 8]>::try_from(&self[..8]).unwrap())
     }
 }
@@ -42,6 +42,63 @@ impl ByteVec {
         self.0.iter()
     }
 
+    /// Replace the bytes in range, growing or shrinking the vector
+    pub fn splice(&mut self, range: std::ops::Range<usize>, with: &[u8]) {
+        self.0.splice(range, with.iter().cloned());
+    }
+
+    pub fn extend_from_slice(&mut self, other: &[u8]) {
+        self.0.extend_from_slice(other);
+    }
+
+    /// Bit at offset, the most significant bit of the first byte is offset 0
+    ///
+    /// Bits past the end are 0.
+    pub fn get_bit(&self, offset: usize) -> bool {
+        match self.0.get(offset / 8) {
+            Some(b) => b & (0x80 >> (offset % 8)) != 0,
+            None => false,
+        }
+    }
+
+    /// Set the bit at offset and return its previous value, the vector is zero padded
+    /// if offset is past the end
+    pub fn set_bit(&mut self, offset: usize, bit: bool) -> bool {
+        let (i, mask) = (offset / 8, 0x80 >> (offset % 8));
+        if i >= self.0.len() {
+            self.0.resize(i + 1, 0);
+        }
+
+        let old = self.0[i] & mask != 0;
+        if bit {
+            self.0[i] |= mask;
+        } else {
+            self.0[i] &= !mask;
+        }
+
+        old
+    }
+
+    /// Number of set bits in the byte range
+    pub fn count_ones(&self, range: std::ops::Range<usize>) -> usize {
+        self.0[range].iter().map(|b| b.count_ones() as usize).sum()
+    }
+
+    /// Offset of the first bit equal to `bit` in the byte range
+    pub fn bit_position(&self, bit: bool, range: std::ops::Range<usize>) -> Option<usize> {
+        let skip = if bit { 0 } else { 0xff };
+        let start = range.start;
+
+        self.0[range].iter().position(|b| *b != skip).map(|i| {
+            let b = if bit {
+                self.0[start + i]
+            } else {
+                !self.0[start + i]
+            };
+            (start + i) * 8 + b.leading_zeros() as usize
+        })
+    }
+
     pub fn extract<T: ?Sized + serde::de::DeserializeOwned>(&self) -> T {
         deserialize_bytevec(&self)
     }
@@ -102,7 +159,12 @@ impl ByteVec {
         f32::from_le_bytes(<[u8; 4]>::try_from(&self[..4]).unwrap())
     }
 
+    /// f32 values, which share the type name f64, are widened
     pub fn as_f64(&self) -> f64 {
+        if self.len() == 4 {
+            return self.as_f32() as f64;
+        }
+
         f64::from_le_bytes(<[u8; 8]>::try_from(&self[..8]).unwrap())
     }
 }
@@ -0,0 +1,93 @@
+//! Helpers for collection values serialized by bincode
+//!
+//! Collections(lists, sets, etc) are stored as a bincode serialized Vec, i.e an u64
+//! element count followed by the elements. Elements are addressed by walking their
+//! byte lengths instead of deserializing the whole collection.
+//!
+
+use std::convert::TryFrom;
+
+use super::{BvObject, ByteVec};
+use crate::utils::{deserialize, serialize};
+
+pub const COUNT_BS: usize = 8;
+
+fn read_u64(v: &[u8]) -> usize {
+    u64::from_le_bytes(<[u8; 8]>::try_from(&v[..8]).unwrap()) as usize
+}
+
+/// Byte length of a serialized Vec<u8>, String, BvString or ByteVec at the start of v
+pub fn bytes_len(v: &[u8]) -> usize {
+    8 + read_u64(v)
+}
+
+/// Byte length of a serialized BvObject at the start of v
+pub fn object_len(v: &[u8]) -> usize {
+    let t_len = bytes_len(v);
+    t_len + bytes_len(&v[t_len..])
+}
+
+pub fn encode_object(o: &BvObject) -> Vec<u8> {
+    serialize(o)
+}
+
+pub fn decode_object(v: &[u8]) -> BvObject {
+    deserialize(v)
+}
+
+/// An empty serialized collection
+pub fn empty() -> Vec<u8> {
+    vec![0; COUNT_BS]
+}
+
+pub fn count(v: &[u8]) -> usize {
+    read_u64(v)
+}
+
+pub fn set_count(v: &mut ByteVec, count: usize) {
+    v[..COUNT_BS].copy_from_slice(&(count as u64).to_le_bytes());
+}
+
+/// Byte ranges of all elements, `elem_len` returns the length of the element at the start of a slice
+pub fn offsets(v: &[u8], elem_len: fn(&[u8]) -> usize) -> Vec<std::ops::Range<usize>> {
+    let mut pos = COUNT_BS;
+    let mut ranges = Vec::with_capacity(count(v));
+
+    for _ in 0..count(v) {
+        let len = elem_len(&v[pos..]);
+        ranges.push(pos..pos + len);
+        pos += len;
+    }
+
+    ranges
+}
+
+/// Resolve a negative index counting from the end
+pub fn normalize_index(index: isize, len: usize) -> Option<usize> {
+    let index = if index < 0 {
+        len as isize + index
+    } else {
+        index
+    };
+
+    if index < 0 || index as usize >= len {
+        return None;
+    }
+
+    Some(index as usize)
+}
+
+/// Resolve an inclusive range of indexes, negative indexes count from the end
+///
+/// Out of range indexes are clamped, None is returned if the range is empty.
+pub fn normalize_range(start: isize, stop: isize, len: usize) -> Option<std::ops::Range<usize>> {
+    let len = len as isize;
+    let start = if start < 0 { len + start } else { start }.max(0);
+    let stop = if stop < 0 { len + stop } else { stop }.min(len - 1);
+
+    if start > stop || start >= len {
+        return None;
+    }
+
+    Some(start as usize..stop as usize + 1)
+}
@@ -0,0 +1,172 @@
+//! Byte layout of values serialized by bincode, derived from their type name
+//!
+//! Supports integers, floats, bool, char, strings, Option, Box, tuples, arrays, slices,
+//! Vec, VecDeque and BTreeSet, nested in any combination. Structs, enums and maps can't be
+//! decoded from their type name.
+
+/// Encoding of a type
+#[derive(Debug, Clone, PartialEq)]
+pub enum Layout {
+    /// Fixed number of bytes, integers, floats and bool
+    Fixed(usize),
+    /// 1-4 bytes of UTF-8
+    Char,
+    /// u64 length followed by the bytes
+    Str,
+    /// u8 tag followed by the value if the tag is 1
+    Option(Box<Layout>),
+    Tuple(Vec<Layout>),
+    /// Elements without a length, arrays are serialized as tuples
+    Array(Box<Layout>, usize),
+    /// u64 length followed by the elements
+    Seq(Box<Layout>),
+}
+
+impl Layout {
+    /// Parse a type name as returned by std::any::type_name
+    pub fn parse(type_name: &str) -> Result<Self, String> {
+        let unsupported = || Err(format!("Unsupported type \"{}\"", type_name));
+        let t = strip_ref(type_name.trim());
+
+        if t.starts_with('(') && t.ends_with(')') {
+            let elements = split_elements(&t[1..t.len() - 1])
+                .into_iter()
+                .map(Layout::parse)
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok(Layout::Tuple(elements));
+        }
+
+        if t.starts_with('[') && t.ends_with(']') {
+            let inner = &t[1..t.len() - 1];
+            return match inner.rfind("; ") {
+                Some(i) if depth_at(inner, i) == 0 => match inner[i + 2..].parse() {
+                    Ok(len) => Ok(Layout::Array(Box::new(Layout::parse(&inner[..i])?), len)),
+                    Err(_) => unsupported(),
+                },
+                _ => Ok(Layout::Seq(Box::new(Layout::parse(inner)?))),
+            };
+        }
+
+        let (path, args) = match t.find('<') {
+            Some(i) if t.ends_with('>') => (&t[..i], split_elements(&t[i + 1..t.len() - 1])),
+            Some(_) => return unsupported(),
+            None => (t, Vec::new()),
+        };
+        let name = path.rsplit("::").next().unwrap();
+
+        Ok(match (name, args.as_slice()) {
+            ("bool", []) | ("i8", []) | ("u8", []) => Layout::Fixed(1),
+            ("i16", []) | ("u16", []) => Layout::Fixed(2),
+            ("i32", []) | ("u32", []) | ("f32", []) => Layout::Fixed(4),
+            ("i64", []) | ("u64", []) | ("f64", []) | ("isize", []) | ("usize", []) => {
+                Layout::Fixed(8)
+            }
+            ("i128", []) | ("u128", []) => Layout::Fixed(16),
+            ("char", []) => Layout::Char,
+            ("str", []) | ("String", []) => Layout::Str,
+            ("Option", [arg]) => Layout::Option(Box::new(Layout::parse(arg)?)),
+            ("Box", [arg]) => Layout::parse(arg)?,
+            ("Vec", [arg]) | ("VecDeque", [arg]) | ("BTreeSet", [arg]) => {
+                Layout::Seq(Box::new(Layout::parse(arg)?))
+            }
+            _ => return unsupported(),
+        })
+    }
+
+    /// Byte length of the value at the start of bytes
+    pub fn encoded_len(&self, bytes: &[u8]) -> Result<usize, String> {
+        let truncated = || Err("Value is shorter than its type".to_string());
+
+        let len = match self {
+            Layout::Fixed(len) => *len,
+            Layout::Char => match bytes.first() {
+                Some(b) if *b < 0x80 => 1,
+                Some(b) if *b < 0xE0 => 2,
+                Some(b) if *b < 0xF0 => 3,
+                Some(_) => 4,
+                None => return truncated(),
+            },
+            Layout::Str => read_len(bytes)?
+                .checked_add(8)
+                .ok_or_else(|| "Invalid string length".to_string())?,
+            Layout::Option(inner) => match bytes.first() {
+                Some(0) => 1,
+                Some(1) => 1 + inner.encoded_len(&bytes[1..])?,
+                Some(_) => return Err("Invalid Option tag".to_string()),
+                None => return truncated(),
+            },
+            Layout::Tuple(elements) => {
+                let mut len = 0;
+                for element in elements {
+                    len += element.encoded_len(bytes.get(len..).unwrap_or(&[]))?;
+                }
+                len
+            }
+            Layout::Array(inner, count) => sum_len(inner, *count, bytes, 0)?,
+            Layout::Seq(inner) => sum_len(inner, read_len(bytes)?, bytes, 8)?,
+        };
+
+        if len > bytes.len() {
+            return truncated();
+        }
+
+        Ok(len)
+    }
+
+    /// Check if every value of the type has the same length
+    pub fn is_fixed(&self) -> bool {
+        match self {
+            Layout::Fixed(_) => true,
+            Layout::Tuple(elements) => elements.iter().all(Layout::is_fixed),
+            Layout::Array(inner, _) => inner.is_fixed(),
+            _ => false,
+        }
+    }
+}
+
+/// Top level elements of a comma separated list of type names
+pub fn split_elements(s: &str) -> Vec<&str> {
+    let mut elements = Vec::new();
+    let mut start = 0;
+
+    for (i, c) in s.char_indices() {
+        if c == ',' && depth_at(s, i) == 0 {
+            elements.push(s[start..i].trim());
+            start = i + 1;
+        }
+    }
+    elements.push(s[start..].trim());
+
+    // "(i8,)" is a tuple of one element
+    elements.retain(|e| !e.is_empty());
+    elements
+}
+
+/// Nesting depth of brackets before byte i
+fn depth_at(s: &str, i: usize) -> i32 {
+    s[..i].chars().fold(0, |depth, c| match c {
+        '(' | '<' | '[' => depth + 1,
+        ')' | '>' | ']' => depth - 1,
+        _ => depth,
+    })
+}
+
+fn strip_ref(t: &str) -> &str {
+    let t = t.trim_start_matches('&');
+    t.strip_prefix("mut ").unwrap_or(t)
+}
+
+fn read_len(bytes: &[u8]) -> Result<usize, String> {
+    match bytes.get(..8) {
+        Some(len) => Ok(crate::utils::deserialize::<u64>(len) as usize),
+        None => Err("Value is shorter than its type".to_string()),
+    }
+}
+
+fn sum_len(inner: &Layout, count: usize, bytes: &[u8], mut len: usize) -> Result<usize, String> {
+    for _ in 0..count {
+        len += inner.encoded_len(bytes.get(len..).unwrap_or(&[]))?;
+    }
+
+    Ok(len)
+}
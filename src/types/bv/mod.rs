@@ -1,17 +1,33 @@
+pub mod bvbitmap;
+pub mod bvhash;
+pub mod bvhll;
 pub mod bvint;
+pub mod bvlist;
 pub mod bvobj;
 pub mod bvobject;
+pub mod bvset;
 pub mod bvstr;
+pub mod bvstream;
 pub mod bvstring;
 pub mod bvtuple;
+pub mod bvzset;
 pub mod byteslice;
 pub mod bytevec;
+pub mod elements;
+pub mod layout;
+pub mod numeric;
 
+pub use bvhash::BvHash;
+pub use bvhll::BvHll;
 pub use bvint::BvInt;
+pub use bvlist::BvList;
 pub use bvobj::BvObj;
 pub use bvobject::BvObject;
+pub use bvset::BvSet;
 pub use bvstr::BvStr;
+pub use bvstream::{BvStream, StreamGroup, StreamId};
 pub use bvstring::BvString;
 pub use bvtuple::BvTuple;
+pub use bvzset::BvZSet;
 pub use byteslice::ByteSlice;
 pub use bytevec::ByteVec;
@@ -0,0 +1,281 @@
+//! Checked and saturating arithmetic on numeric BvObjects
+//!
+//! # Type promotion
+//! * integer and integer results in the type of the left hand side, the right hand side may
+//!   be any integer type
+//! * a float and an integer results in the type of the float
+//! * f32 and f64 results in f64
+//!
+//! Checked operations are Err on overflow, saturating operations clamp to the bounds of the
+//! result type. Division by zero and NaN results are Err in both modes, integer division
+//! truncates towards zero.
+
+use std::cmp::Ordering;
+
+use super::BvObject;
+use crate::utils::serialize_object;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NumOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Min,
+    Max,
+}
+
+/// Handling of results outside the bounds of the result type
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overflow {
+    Checked,
+    Saturating,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum IntType {
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+}
+
+impl IntType {
+    fn name(self) -> &'static str {
+        match self {
+            IntType::I8 => "i8",
+            IntType::I16 => "i16",
+            IntType::I32 => "i32",
+            IntType::I64 => "i64",
+            IntType::I128 => "i128",
+            IntType::U8 => "u8",
+            IntType::U16 => "u16",
+            IntType::U32 => "u32",
+            IntType::U64 => "u64",
+            IntType::U128 => "u128",
+        }
+    }
+
+    fn bits(self) -> u32 {
+        match self {
+            IntType::I8 | IntType::U8 => 8,
+            IntType::I16 | IntType::U16 => 16,
+            IntType::I32 | IntType::U32 => 32,
+            IntType::I64 | IntType::U64 => 64,
+            IntType::I128 | IntType::U128 => 128,
+        }
+    }
+
+    fn is_signed(self) -> bool {
+        matches!(
+            self,
+            IntType::I8 | IntType::I16 | IntType::I32 | IntType::I64 | IntType::I128
+        )
+    }
+
+    /// Magnitude of the largest positive value
+    fn max(self) -> u128 {
+        if self.is_signed() {
+            (1 << (self.bits() - 1)) - 1
+        } else {
+            u128::MAX >> (128 - self.bits())
+        }
+    }
+
+    /// Magnitude of the smallest negative value
+    fn min(self) -> u128 {
+        if self.is_signed() {
+            1 << (self.bits() - 1)
+        } else {
+            0
+        }
+    }
+
+    /// Value in bounds of the type as sign and magnitude
+    fn encode(self, neg: bool, mag: u128) -> BvObject {
+        let n = if neg {
+            (mag as i128).wrapping_neg()
+        } else {
+            mag as i128
+        };
+
+        match self {
+            IntType::I8 => serialize_object(&(n as i8)),
+            IntType::I16 => serialize_object(&(n as i16)),
+            IntType::I32 => serialize_object(&(n as i32)),
+            IntType::I64 => serialize_object(&(n as i64)),
+            IntType::I128 => serialize_object(&n),
+            IntType::U8 => serialize_object(&(mag as u8)),
+            IntType::U16 => serialize_object(&(mag as u16)),
+            IntType::U32 => serialize_object(&(mag as u32)),
+            IntType::U64 => serialize_object(&(mag as u64)),
+            IntType::U128 => serialize_object(&mag),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Number {
+    /// Sign and magnitude, zero is never negative
+    Int(IntType, bool, u128),
+    F32(f32),
+    F64(f64),
+}
+
+impl Number {
+    fn decode(v: &BvObject) -> Option<Self> {
+        let signed = |t, n: i128| Number::Int(t, n < 0, n.unsigned_abs());
+        let unsigned = |t, n: u128| Number::Int(t, false, n);
+
+        Some(match v.type_name().as_str() {
+            "i8" => signed(IntType::I8, v.as_i8() as i128),
+            "i16" => signed(IntType::I16, v.as_i16() as i128),
+            "i32" => signed(IntType::I32, v.as_i32() as i128),
+            "i64" => signed(IntType::I64, v.as_i64() as i128),
+            "i128" => signed(IntType::I128, v.as_i128()),
+            "u8" => unsigned(IntType::U8, v.as_u8() as u128),
+            "u16" => unsigned(IntType::U16, v.as_u16() as u128),
+            "u32" => unsigned(IntType::U32, v.as_u32() as u128),
+            "u64" => unsigned(IntType::U64, v.as_u64() as u128),
+            "u128" => unsigned(IntType::U128, v.as_u128()),
+            // f32 is stored with the type name f64, see normalize_type_name
+            "f64" if v.len() == 4 => Number::F32(v.as_f32()),
+            "f64" => Number::F64(v.as_f64()),
+            _ => return None,
+        })
+    }
+
+    fn as_f64(self) -> f64 {
+        match self {
+            Number::Int(_, neg, mag) if neg => -(mag as f64),
+            Number::Int(_, _, mag) => mag as f64,
+            Number::F32(f) => f as f64,
+            Number::F64(f) => f,
+        }
+    }
+}
+
+/// Check if v is an integer or a float
+pub fn is_numeric(v: &BvObject) -> bool {
+    Number::decode(v).is_some()
+}
+
+/// Zero of the same type as v
+pub fn zero(v: &BvObject) -> Result<BvObject, String> {
+    match decode(v)? {
+        Number::Int(t, _, _) => Ok(t.encode(false, 0)),
+        Number::F32(_) => Ok(serialize_object(&0f32)),
+        Number::F64(_) => Ok(serialize_object(&0f64)),
+    }
+}
+
+/// Calculate `lhs op rhs`, Err if either side isn't numeric or the result is invalid
+pub fn calc(
+    lhs: &BvObject,
+    op: NumOp,
+    rhs: &BvObject,
+    overflow: Overflow,
+) -> Result<BvObject, String> {
+    match (decode(lhs)?, decode(rhs)?) {
+        (Number::Int(t, a_neg, a), Number::Int(_, b_neg, b)) => {
+            calc_int(t, (a_neg, a), op, (b_neg, b), overflow)
+        }
+        (a @ Number::F64(_), b) | (a, b @ Number::F64(_)) => {
+            let r = calc_float(a.as_f64(), op, b.as_f64(), "f64", overflow)?;
+            Ok(serialize_object(&r))
+        }
+        (a, b) => {
+            let r = calc_float(a.as_f64(), op, b.as_f64(), "f32", overflow)?;
+            Ok(serialize_object(&(r as f32)))
+        }
+    }
+}
+
+fn decode(v: &BvObject) -> Result<Number, String> {
+    Number::decode(v)
+        .ok_or_else(|| format!("Expected a numeric value found type \"{}\"", v.type_name()))
+}
+
+fn calc_int(
+    t: IntType,
+    a: (bool, u128),
+    op: NumOp,
+    b: (bool, u128),
+    overflow: Overflow,
+) -> Result<BvObject, String> {
+    // Magnitude is None if it overflowed u128, which is out of bounds of every type
+    let (neg, mag) = match op {
+        NumOp::Add => add(a, b),
+        NumOp::Sub => add(a, (!b.0, b.1)),
+        NumOp::Mul => (a.0 != b.0, a.1.checked_mul(b.1)),
+        NumOp::Div if b.1 == 0 => return Err("Division by zero".to_string()),
+        NumOp::Div => (a.0 != b.0, Some(a.1 / b.1)),
+        NumOp::Min if cmp(a, b) == Ordering::Greater => (b.0, Some(b.1)),
+        NumOp::Max if cmp(a, b) == Ordering::Less => (b.0, Some(b.1)),
+        NumOp::Min | NumOp::Max => (a.0, Some(a.1)),
+    };
+
+    let bound = if neg { t.min() } else { t.max() };
+    let mag = match (mag, overflow) {
+        (Some(mag), _) if mag <= bound => mag,
+        (_, Overflow::Saturating) => bound,
+        (_, Overflow::Checked) => return Err(format!("Result overflows type \"{}\"", t.name())),
+    };
+
+    Ok(t.encode(neg && mag != 0, mag))
+}
+
+fn add(a: (bool, u128), b: (bool, u128)) -> (bool, Option<u128>) {
+    if a.0 == b.0 {
+        (a.0, a.1.checked_add(b.1))
+    } else if a.1 >= b.1 {
+        (a.0, Some(a.1 - b.1))
+    } else {
+        (b.0, Some(b.1 - a.1))
+    }
+}
+
+fn cmp(a: (bool, u128), b: (bool, u128)) -> Ordering {
+    match (a.0, b.0) {
+        (false, false) => a.1.cmp(&b.1),
+        (true, true) => b.1.cmp(&a.1),
+        (true, false) if a.1 == 0 && b.1 == 0 => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) if a.1 == 0 && b.1 == 0 => Ordering::Equal,
+        (false, true) => Ordering::Greater,
+    }
+}
+
+fn calc_float(a: f64, op: NumOp, b: f64, t: &str, overflow: Overflow) -> Result<f64, String> {
+    let r = match op {
+        NumOp::Add => a + b,
+        NumOp::Sub => a - b,
+        NumOp::Mul => a * b,
+        NumOp::Div if b == 0.0 => return Err("Division by zero".to_string()),
+        NumOp::Div => a / b,
+        NumOp::Min => a.min(b),
+        NumOp::Max => a.max(b),
+    };
+
+    let bound = if t == "f32" {
+        f32::MAX as f64
+    } else {
+        f64::MAX
+    };
+    if r.is_nan() {
+        return Err("Result is not a number".to_string());
+    }
+
+    match overflow {
+        _ if r.abs() <= bound => Ok(r),
+        Overflow::Saturating => Ok(bound.copysign(r)),
+        Overflow::Checked => Err(format!("Result overflows type \"{}\"", t)),
+    }
+}
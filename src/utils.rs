@@ -18,6 +18,11 @@ pub fn normalize_type_name(tn: &[u8]) -> &[u8] {
     }
 }
 
+/// Type name of T as stored in BvObject
+pub fn type_name_of<T: ?Sized>() -> &'static [u8] {
+    normalize_type_name(std::any::type_name::<T>().as_bytes())
+}
+
 pub fn serialize<T: ?Sized + serde::ser::Serialize>(o: &T) -> Vec<u8> {
     bincode::serialize(o).unwrap()
 }
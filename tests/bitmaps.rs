@@ -0,0 +1,60 @@
+use icbiadb::kv::types::BitOp;
+use icbiadb::storage::BTreeMap;
+
+#[test]
+fn set_and_get_bits() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    assert!(!db.setbit("b", 7, true).unwrap());
+    assert!(db.setbit("b", 7, true).unwrap());
+    assert!(!db.setbit("b", 20, true).unwrap());
+    assert!(db.getbit("b", 7).unwrap());
+    assert!(!db.getbit("b", 1000).unwrap());
+    assert!(!db.getbit("missing", 0).unwrap());
+    assert_eq!(db.get("b").unwrap().len(), 3);
+
+    db.set("s", "str").unwrap();
+    assert!(db.setbit("s", 1, true).is_err());
+}
+
+#[test]
+fn offset_limit() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    assert!(db.setbit("b", usize::MAX, true).is_err());
+    assert!(db.setbit("b", 1 << 40, true).is_err());
+    assert!(!db.has_key("b"));
+}
+
+#[test]
+fn count_and_position() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.setbit("b", 7, true).unwrap();
+    db.setbit("b", 20, true).unwrap();
+    assert_eq!(db.bitcount("b", 0, -1).unwrap(), 2);
+    assert_eq!(db.bitcount("b", 1, -1).unwrap(), 1);
+    assert_eq!(db.bitpos("b", true, 0, -1).unwrap(), Some(7));
+    assert_eq!(db.bitpos("b", true, 1, -1).unwrap(), Some(20));
+    assert_eq!(db.bitpos("b", false, 0, -1).unwrap(), Some(0));
+}
+
+#[test]
+fn bit_operations() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.setbit("b", 7, true).unwrap();
+    db.setbit("b", 20, true).unwrap();
+    db.setbit("c", 7, true).unwrap();
+    db.setbit("c", 0, true).unwrap();
+
+    assert_eq!(db.bitop(BitOp::And, "d", &["b", "c"]).unwrap(), 3);
+    assert_eq!(db.bitcount("d", 0, -1).unwrap(), 1);
+    db.bitop(BitOp::Or, "d", &["b", "c"]).unwrap();
+    assert_eq!(db.bitcount("d", 0, -1).unwrap(), 3);
+    db.bitop(BitOp::Xor, "d", &["b", "c"]).unwrap();
+    assert_eq!(db.bitcount("d", 0, -1).unwrap(), 2);
+    db.bitop(BitOp::Not, "d", &["c"]).unwrap();
+    assert_eq!(db.bitcount("d", 0, -1).unwrap(), 6);
+    assert!(db.bitop(BitOp::Not, "d", &["b", "c"]).is_err());
+
+    // Missing sources give an empty result, the destination is removed
+    assert_eq!(db.bitop(BitOp::Or, "d", &["x"]).unwrap(), 0);
+    assert!(!db.has_key("d"));
+}
@@ -0,0 +1,94 @@
+use icbiadb::kv::types::{CacheConfig, EventKind, EvictionPolicy};
+use icbiadb::storage::BTreeMap;
+
+#[test]
+fn lru() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    let events = db.watch_prefix("");
+    db.set_cache(CacheConfig::new(EvictionPolicy::Lru).max_keys(3));
+    db.set("a", 1).unwrap();
+    db.set("b", 2).unwrap();
+    db.set("c", 3).unwrap();
+    db.get("a");
+    db.set("d", 4).unwrap();
+
+    assert!(db.get("b").is_none());
+    assert!(db.get("a").is_some());
+    assert_eq!(db.cache_stats().unwrap().evictions, 1);
+    assert_eq!(db.cache_stats().unwrap().keys, 3);
+    assert!(events
+        .try_iter()
+        .any(|e| e.kind == EventKind::Evict && e.key.as_str() == "b"));
+
+    // Overwrites count as use
+    db.set("c", 5).unwrap();
+    db.set("e", 5).unwrap();
+    assert!(db.get("d").is_none());
+    assert!(db.get("c").is_some());
+}
+
+#[test]
+fn lfu_and_byte_budget() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set_cache(CacheConfig::new(EvictionPolicy::Lfu).max_bytes(55));
+    db.set("a", 1i32).unwrap(); // 1 + 3 + 4
+    db.get("a");
+    db.get("a");
+    db.set("b", 2i32).unwrap();
+    db.set("c", "x".repeat(30)).unwrap(); // 1 + 3 + 38
+
+    assert!(db.get("b").is_none());
+    assert_eq!(db.cache_stats().unwrap().bytes, 50);
+    assert!(db.set("big", "x".repeat(100)).is_err());
+    assert!(db.append("c", &"y".repeat(20)).is_err());
+    assert!(db.get("c").is_some());
+}
+
+#[test]
+fn lfu_ties_evict_least_recent() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set_cache(CacheConfig::new(EvictionPolicy::Lfu).max_keys(2));
+    db.set("a", 1).unwrap();
+    db.set("b", 1).unwrap();
+    db.get("b");
+    db.get("a");
+    db.set("c", 1).unwrap();
+
+    assert!(db.get("b").is_none());
+    assert!(db.get("a").is_some());
+    assert_eq!(db.cache_stats().unwrap().evictions, 1);
+}
+
+#[test]
+fn volatile_ttl() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set("x", 1).unwrap();
+    db.set_ex("t1", 1, std::time::Duration::from_secs(100))
+        .unwrap();
+    db.set_ex("t2", 1, std::time::Duration::from_secs(10))
+        .unwrap();
+    db.set_cache(CacheConfig::new(EvictionPolicy::VolatileTtl).max_keys(1));
+    assert_eq!(db.len(), 1);
+    assert!(db.get("x").is_some());
+
+    // Nothing left to evict, the budget is exceeded
+    db.set("y", 1).unwrap();
+    assert_eq!(db.len(), 2);
+    assert_eq!(db.cache_stats().unwrap().evictions, 2);
+}
+
+#[test]
+fn random() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set_cache(CacheConfig::new(EvictionPolicy::Random).max_keys(10));
+    for i in 0..100 {
+        db.set(format!("k{}", i), i).unwrap();
+    }
+    assert_eq!(db.len(), 10);
+    assert!(db.get("k99").is_some());
+    assert_eq!(db.cache_stats().unwrap().evictions, 90);
+
+    assert!(db.remove_cache());
+    db.set("more", 1).unwrap();
+    assert_eq!(db.len(), 11);
+}
@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use icbiadb::kv::types::EventKind;
+use icbiadb::prelude::*;
+use icbiadb::storage::BTreeMap;
+
+#[test]
+fn expired_keys_are_invisible() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    let events = db.watch_prefix("s");
+    db.set_ex("s1", 1, Duration::from_millis(30)).unwrap();
+    db.set_ex("s2", 1, Duration::from_secs(100)).unwrap();
+    db.set("s3", 3).unwrap();
+    assert_eq!(db.len(), 3);
+    assert!(db.ttl("s1").is_some());
+    assert!(db.ttl("s3").is_none());
+
+    std::thread::sleep(Duration::from_millis(40));
+    assert_eq!(db.len(), 2);
+    assert!(db.get("s1").is_none());
+    assert!(!db.has_key("s1"));
+    assert_eq!(db.starts_with("s").len(), 2);
+
+    assert_eq!(db.sweep_expired(), 1);
+    assert!(events
+        .try_iter()
+        .any(|e| e.kind == EventKind::Expire && e.key.as_str() == "s1"));
+}
+
+#[test]
+fn expire_and_persist() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set_ex("a", 1, Duration::from_secs(100)).unwrap();
+    db.set("b", 1).unwrap();
+    assert!(db.persist("a"));
+    assert!(!db.persist("a"));
+    assert!(db.expire("b", Duration::from_secs(50)));
+    assert!(!db.expire("missing", Duration::from_secs(50)));
+    assert!(db.ttl("b").unwrap() <= Duration::from_secs(50));
+
+    // Overwriting a key removes its timeout
+    db.set("b", 2).unwrap();
+    assert!(db.ttl("b").is_none());
+}
+
+#[test]
+fn expiry_is_persisted() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set("a", 1).unwrap();
+    db.set_ex("b", "str", Duration::from_secs(100)).unwrap();
+
+    let mut buf = std::io::Cursor::new(Vec::new());
+    db.commit_to(&mut buf).unwrap();
+    buf.set_position(0);
+    let db = icbiadb::kv::read_from::<_, BTreeMap>(buf).unwrap();
+    assert_eq!(db.len(), 2);
+    assert!(db.ttl("a").is_none());
+    assert!(db.ttl("b").is_some());
+}
+
+#[test]
+fn import_removes_timeouts() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set_ex("a", 1, Duration::from_millis(10)).unwrap();
+    db.set_ex("b", 1, Duration::from_millis(10)).unwrap();
+    db.import(vec![]);
+    std::thread::sleep(Duration::from_millis(20));
+    assert_eq!(db.len(), 0);
+
+    let mut other = icbiadb::kv::mem::<BTreeMap>();
+    other.set("a", 1).unwrap();
+    db.import(other.export());
+    assert_eq!(db.len(), 1);
+    assert!(db.ttl("a").is_none());
+}
+
+#[test]
+fn swap_respects_ttl() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set_ex("e", 1u32, Duration::from_millis(1)).unwrap();
+    db.set_ex("live", 1u32, Duration::from_secs(100)).unwrap();
+    std::thread::sleep(Duration::from_millis(10));
+
+    assert!(db.swap("e", 2u32).is_err());
+    assert!(db.get("e").is_none());
+
+    assert_eq!(db.swap("live", 2u32).unwrap().as_u32(), 1);
+    assert!(db.ttl("live").is_some());
+}
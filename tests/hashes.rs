@@ -0,0 +1,80 @@
+use icbiadb::storage::BTreeMap;
+
+#[test]
+fn fields() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    assert!(db.hset("h", "title", "hello").unwrap());
+    assert!(db.hset("h", "author", "me").unwrap());
+    assert!(!db.hset("h", "title", "world!").unwrap());
+    assert_eq!(
+        db.hget("h", "title").unwrap().unwrap().extract::<String>(),
+        "world!"
+    );
+    assert!(db.hget("h", "missing").unwrap().is_none());
+    assert_eq!(db.hkeys("h").unwrap(), vec!["author", "title"]);
+    assert!(db.hexists("h", "author").unwrap());
+    assert_eq!(db.hlen("h").unwrap(), 2);
+    assert_eq!(db.hgetall("h").unwrap().len(), 2);
+
+    assert!(db.hdel("h", "title").unwrap());
+    assert!(!db.hdel("h", "title").unwrap());
+    assert!(db.hdel("h", "author").unwrap());
+    assert!(!db.has_key("h"));
+}
+
+#[test]
+fn incr_keeps_integer_type() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    assert_eq!(db.hincrby("h", "views", 5).unwrap(), 5);
+    assert_eq!(db.hincrby("h", "views", -7).unwrap(), -2);
+
+    db.hset("h", "small", 250u8).unwrap();
+    assert!(db.hincrby("h", "small", 10).is_err());
+    assert_eq!(db.hincrby("h", "small", 5).unwrap(), 255);
+    assert_eq!(
+        db.hget("h", "small").unwrap().unwrap().type_name().as_str(),
+        "u8"
+    );
+
+    db.hset("h", "size", 1usize).unwrap();
+    assert_eq!(db.hincrby("h", "size", 1).unwrap(), 2);
+    db.hset("h", "title", "x").unwrap();
+    assert!(db.hincrby("h", "title", 1).is_err());
+}
+
+#[test]
+fn scan_fields() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    for f in &["author", "small", "title", "views"] {
+        db.hset("h", f, 1).unwrap();
+    }
+
+    let fields = |pattern| -> Vec<String> {
+        db.hscan("h", pattern)
+            .unwrap()
+            .into_iter()
+            .map(|(f, _)| f)
+            .collect()
+    };
+    assert_eq!(fields("*t*"), vec!["author", "title"]);
+    assert_eq!(fields("[s-v]?*"), vec!["small", "title", "views"]);
+}
+
+#[test]
+fn field_type_change() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.hset("h", "f", 1u32).unwrap();
+    assert!(!db.hset("h", "f", 1u32).unwrap());
+    assert!(!db.hset("h", "f", 1i32).unwrap());
+    assert_eq!(
+        db.hget("h", "f").unwrap().unwrap().type_name().as_str(),
+        "i32"
+    );
+
+    db.hset("h", "b", 0u8).unwrap();
+    db.hset("h", "b", false).unwrap();
+    assert_eq!(
+        db.hget("h", "b").unwrap().unwrap().type_name().as_str(),
+        "bool"
+    );
+}
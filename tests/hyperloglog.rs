@@ -0,0 +1,45 @@
+use icbiadb::storage::BTreeMap;
+use icbiadb::types::bv::BvHll;
+
+fn assert_close(estimate: u64, actual: u64) {
+    let error = (estimate as f64 - actual as f64).abs() / actual as f64;
+    assert!(error < 0.03, "estimate {} of {}", estimate, actual);
+}
+
+#[test]
+fn count_and_merge() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    let a: Vec<u64> = (0..100_000).collect();
+    let b: Vec<u64> = (50_000..150_000).collect();
+    assert!(db.pfadd("a", &a).unwrap());
+    db.pfadd("b", &b).unwrap();
+    assert!(!db.pfadd("a", &a[..10]).unwrap());
+
+    assert_close(db.pfcount(&["a"]).unwrap(), 100_000);
+    let union = db.pfcount(&["a", "b"]).unwrap();
+    assert_close(union, 150_000);
+
+    db.pfmerge("u", &["a", "b"]).unwrap();
+    assert_eq!(db.pfcount(&["u"]).unwrap(), union);
+}
+
+#[test]
+fn small_cardinalities() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.pfadd("small", &["x", "y", "z", "x"]).unwrap();
+    assert_eq!(db.pfcount(&["small"]).unwrap(), 3);
+    assert_eq!(db.pfcount(&["none"]).unwrap(), 0);
+
+    db.set("s", 1).unwrap();
+    assert!(db.pfadd("s", &[1]).is_err());
+}
+
+#[test]
+fn in_place() {
+    let mut v = BvHll::new_bytevec();
+    let mut hll = BvHll::from(&mut v).unwrap();
+    hll.add(b"one");
+    hll.add(b"two");
+    hll.add(b"one");
+    assert_eq!(hll.count(), 2);
+}
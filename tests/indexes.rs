@@ -0,0 +1,141 @@
+use icbiadb::storage::BTreeMap;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+struct Book {
+    title: String,
+    year: u16,
+}
+
+fn populated() -> icbiadb::KvDb<BTreeMap> {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set(
+        "book:1",
+        Book {
+            title: "a".into(),
+            year: 1990,
+        },
+    )
+    .unwrap();
+    db.create_field_index::<Book, _, _, _>("by_title", "book:", "title")
+        .unwrap();
+    db.create_field_index::<Book, _, _, _>("by_year", "book:", "year")
+        .unwrap();
+    db.create_index("by_n", "n:").unwrap();
+
+    db.set(
+        "book:2",
+        Book {
+            title: "b".into(),
+            year: 2000,
+        },
+    )
+    .unwrap();
+    db.set(
+        "book:3",
+        Book {
+            title: "a".into(),
+            year: 2010,
+        },
+    )
+    .unwrap();
+    db.set("n:1", -5i64).unwrap();
+    db.set("n:2", 3u8).unwrap();
+    db.set("n:3", 100i32).unwrap();
+    db
+}
+
+#[test]
+fn field_and_value_lookups() {
+    let mut db = populated();
+    assert!(db.create_index("by_n", "n:").is_err());
+    assert_eq!(db.index("by_title").unwrap().get("a").len(), 2);
+    assert_eq!(
+        db.index("by_year").unwrap().range(1995u16, 2020u16).len(),
+        2
+    );
+
+    let r = db.index("by_n").unwrap().range(-10, 50);
+    assert_eq!(r.len(), 2);
+    assert_eq!(r[0].0.as_str(), "n:1");
+
+    db.del("book:3");
+    assert_eq!(db.index("by_title").unwrap().get("a").len(), 1);
+    db.incr("n:2").unwrap();
+    assert_eq!(db.index("by_n").unwrap().get(4u8).len(), 1);
+    assert!(db.index("none").is_err());
+}
+
+#[test]
+fn survive_reload() {
+    let mut db = populated();
+    let mut buf = std::io::Cursor::new(Vec::new());
+    db.commit_to(&mut buf).unwrap();
+    buf.set_position(0);
+
+    let db = icbiadb::kv::read_from::<_, BTreeMap>(buf).unwrap();
+    assert_eq!(db.index("by_title").unwrap().get("b").len(), 1);
+    assert_eq!(db.index("by_n").unwrap().len(), 3);
+}
+
+#[test]
+fn large_unsigned_values() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.create_index("by_n", "n:").unwrap();
+    db.set("n:max", u128::MAX).unwrap();
+    db.set("n:big", i128::MAX as u128 + 1).unwrap();
+    db.set("n:imax", i128::MAX).unwrap();
+    db.set("n:neg", -1i8).unwrap();
+
+    let index = db.index("by_n").unwrap();
+    assert_eq!(index.get(u128::MAX).len(), 1);
+    assert_eq!(index.get(u128::MAX)[0].0.as_str(), "n:max");
+    let keys = |r: Vec<(&icbiadb::types::BvString, &icbiadb::types::BvObject)>| {
+        r.iter().map(|(k, _)| k.to_string()).collect::<Vec<_>>()
+    };
+    assert_eq!(
+        keys(index.range(0u128, u128::MAX)),
+        vec!["n:imax", "n:big", "n:max"]
+    );
+    assert_eq!(
+        keys(index.range(i128::MIN, i128::MAX)),
+        vec!["n:neg", "n:imax"]
+    );
+}
+
+#[test]
+fn short_raw_strings() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.create_index("by_s", "s:").unwrap();
+    db.set_raw("s:1", "str", vec![1, 2, 3]).unwrap();
+    db.set("s:2", "x").unwrap();
+    assert_eq!(db.index("by_s").unwrap().len(), 2);
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct User {
+    age: u32,
+}
+
+#[test]
+fn swap_reindexes_fields() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.create_field_index::<User, _, _, _>("age", "user:", "age")
+        .unwrap();
+    db.set("user:1", User { age: 30 }).unwrap();
+    db.swap("user:1", User { age: 31 }).unwrap();
+
+    let index = db.index("age").unwrap();
+    assert!(index.get(30u32).is_empty());
+    assert_eq!(index.get(31u32).len(), 1);
+}
+
+#[test]
+fn skip_undecodable_fields() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set_raw("user:1", std::any::type_name::<User>(), vec![1])
+        .unwrap();
+    db.set("user:2", User { age: 30 }).unwrap();
+    db.create_field_index::<User, _, _, _>("age", "user:", "age")
+        .unwrap();
+    assert_eq!(db.index("age").unwrap().len(), 1);
+}
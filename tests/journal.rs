@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use icbiadb::if_not_exists_create;
+use icbiadb::kv::types::KeySchema;
+use icbiadb::storage::BTreeMap;
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Book {
+    title: String,
+}
+
+fn article(title: &str) -> icbiadb::TableRow {
+    let mut row = icbiadb::TableRow::default();
+    row.set_col("title", title);
+    row
+}
+
+#[test]
+fn kv_undo_redo() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.enable_journal(2);
+    db.set("a", 1).unwrap();
+    db.incr("a").unwrap();
+    db.del("a");
+    assert_eq!(db.history().len(), 2);
+
+    db.undo().unwrap();
+    assert!(db.get("a").unwrap() == &2);
+    db.undo().unwrap();
+    assert!(db.get("a").unwrap() == &1);
+    assert!(db.undo().unwrap().is_none());
+
+    db.redo().unwrap();
+    assert!(db.get("a").unwrap() == &2);
+
+    // New operations clear the redo history
+    db.set("b", 1).unwrap();
+    assert!(db.redo().unwrap().is_none());
+}
+
+#[test]
+fn kv_undo_restores_expiry_and_field_indexes() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.create_field_index::<Book, _, _, _>("by_title", "book:", "title")
+        .unwrap();
+    db.enable_journal(10);
+
+    db.set("book:1", Book { title: "a".into() }).unwrap();
+    db.set_ex("t", 1, Duration::from_secs(100)).unwrap();
+    db.set("book:1", Book { title: "b".into() }).unwrap();
+    db.set("t", 2).unwrap();
+    assert!(db.ttl("t").is_none());
+
+    db.undo().unwrap();
+    assert!(db.get("t").unwrap() == &1);
+    assert!(db.ttl("t").is_some());
+
+    db.undo().unwrap();
+    assert_eq!(db.index("by_title").unwrap().get("a").len(), 1);
+    assert_eq!(db.index("by_title").unwrap().get("b").len(), 0);
+
+    db.redo().unwrap();
+    assert_eq!(db.index("by_title").unwrap().get("b").len(), 1);
+    db.redo().unwrap();
+    assert!(db.ttl("t").is_none());
+}
+
+#[test]
+fn kv_undo_validates_schemas() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.enable_journal(10);
+    db.set("s", "a long value").unwrap();
+    db.set("s", "short").unwrap();
+    db.key_schema(KeySchema::new("s").max_len(5));
+
+    assert!(db.undo().is_err());
+    assert_eq!(db.get_value::<String>("s"), "short");
+    assert_eq!(db.history().len(), 2);
+}
+
+#[test]
+fn table_undo_redo() {
+    let mut db = icbiadb::table::mem();
+    db.enable_journal(10);
+    if_not_exists_create! {db, "articles", (title: String[unique])};
+
+    db.insert_row("articles", article("a")).unwrap();
+    db.undo().unwrap();
+    assert_eq!(db.rows("articles").len(), 0);
+    db.redo().unwrap();
+    assert_eq!(db.rows("articles").len(), 1);
+}
+
+#[test]
+fn table_undo_after_recreate() {
+    let mut db = icbiadb::table::mem();
+    db.enable_journal(10);
+    if_not_exists_create! {db, "articles", (title: String)};
+    db.insert_row("articles", article("a")).unwrap();
+    db.insert_row("articles", article("b")).unwrap();
+
+    db.remove("articles");
+    if_not_exists_create! {db, "articles", (title: String)};
+    assert!(db.undo().is_none());
+    assert!(db.redo().is_none());
+
+    db.insert_row("articles", article("c")).unwrap();
+    db.undo().unwrap();
+    db.remove("articles");
+    assert!(db.redo().is_none());
+}
@@ -0,0 +1,58 @@
+use icbiadb::kv::types::{KeySchema, KvRule};
+use icbiadb::storage::BTreeMap;
+
+#[test]
+fn auto_increment() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.key_config("my_integers", ":", KvRule::AutoIncrement);
+    assert_eq!(db.store("my_integers", 20).unwrap(), "my_integers:0");
+    assert_eq!(db.store("my_integers", 34).unwrap(), "my_integers:1");
+
+    // Existing keys are skipped
+    db.set("my_integers:2", 1).unwrap();
+    assert_eq!(db.store("my_integers", 106).unwrap(), "my_integers:3");
+    assert!(db.store("nope", 1).is_err());
+}
+
+#[test]
+fn failed_store_keeps_counter() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.key_config("n", ":", KvRule::AutoIncrement);
+    db.key_schema(KeySchema::new("n:*").type_name("i32"));
+    assert!(db.store("n", "not an i32").is_err());
+    assert_eq!(db.key_rule("n").unwrap().counter, 0);
+    assert_eq!(db.store("n", 1).unwrap(), "n:0");
+}
+
+#[test]
+fn timestamp_and_uuid() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.key_config("ev", "/", KvRule::Timestamp);
+    let a = db.store("ev", 1).unwrap();
+    let b = db.store("ev", 2).unwrap();
+    assert!(a < b, "{} {}", a, b);
+
+    db.key_config("u", ":", KvRule::Uuid);
+    let u1 = db.store("u", 1).unwrap();
+    let u2 = db.store("u", 1).unwrap();
+    assert_ne!(u1, u2);
+    assert_eq!(u1.len(), 2 + 36);
+    assert_eq!(&u1[2 + 14..2 + 15], "4");
+}
+
+#[test]
+fn rules_are_persisted() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.key_config("n", ":", KvRule::AutoIncrement);
+    db.key_config("u", ":", KvRule::Uuid);
+    db.store("n", 1).unwrap();
+
+    let mut buf = std::io::Cursor::new(Vec::new());
+    db.commit_to(&mut buf).unwrap();
+    buf.set_position(0);
+    let mut db = icbiadb::kv::read_from::<_, BTreeMap>(buf).unwrap();
+    assert_eq!(db.store("n", 5).unwrap(), "n:1");
+    assert_eq!(db.key_rule("u").unwrap().rule, KvRule::Uuid);
+    assert!(db.remove_key_config("u"));
+    assert!(db.key_rule("u").is_none());
+}
@@ -0,0 +1,71 @@
+use icbiadb::storage::BTreeMap;
+use icbiadb::types::BvObject;
+
+#[test]
+fn push_pop() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    assert_eq!(db.rpush("l", 1).unwrap(), 1);
+    assert_eq!(db.rpush("l", "two").unwrap(), 2);
+    assert_eq!(db.lpush("l", 0u8).unwrap(), 3);
+    assert_eq!(db.llen("l").unwrap(), 3);
+
+    assert_eq!(db.lpop("l").unwrap().unwrap().extract::<u8>(), 0);
+    assert!(db.rpop("l").unwrap().unwrap().as_str() == "two");
+    assert!(db.rpop("l").unwrap().unwrap() == 1);
+
+    // Empty lists are removed
+    assert!(!db.has_key("l"));
+    assert!(db.lpop("l").unwrap().is_none());
+}
+
+#[test]
+fn range_and_index() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    for i in 0..5 {
+        db.rpush("l", i).unwrap();
+    }
+
+    let r = db.lrange("l", 1, -2).unwrap();
+    assert_eq!(r.len(), 3);
+    assert!(r[0] == 1 && r[2] == 3);
+    assert!(db.lindex("l", -1).unwrap().unwrap() == 4);
+    assert!(db.lindex("l", 10).unwrap().is_none());
+    assert!(db.lrange("l", 10, 20).unwrap().is_empty());
+
+    let v: Vec<BvObject> = db.get("l").unwrap().extract();
+    assert_eq!(v.len(), 5);
+}
+
+#[test]
+fn set_and_trim() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.rpush("l", 1).unwrap();
+    db.rpush("l", "two").unwrap();
+    db.rpush("l", 3).unwrap();
+    db.lset("l", 1, "a much longer string").unwrap();
+    assert!(db.lindex("l", 1).unwrap().unwrap().as_str() == "a much longer string");
+    assert!(db.lset("l", 5, 1).is_err());
+
+    db.ltrim("l", 1, 5).unwrap();
+    assert_eq!(db.llen("l").unwrap(), 2);
+    db.ltrim("l", 5, 6).unwrap();
+    assert!(!db.has_key("l"));
+}
+
+#[test]
+fn wrong_type() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set("s", "x").unwrap();
+    assert!(db.lpush("s", 1).is_err());
+    assert!(db.llen("s").is_err());
+}
+
+#[test]
+fn undo_push() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.enable_journal(10);
+    db.rpush("l", 1).unwrap();
+    db.rpush("l", 2).unwrap();
+    db.undo().unwrap();
+    assert_eq!(db.llen("l").unwrap(), 1);
+}
@@ -0,0 +1,77 @@
+use icbiadb::kv::types::KeySchema;
+use icbiadb::storage::BTreeMap;
+use icbiadb::types::bv::BvObject;
+
+#[test]
+fn builtin_operators() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    assert!(db.merge("c:1", 1).is_err());
+    assert!(db.set_merge_operator("c:*", "nope").is_err());
+    db.set_merge_operator("c:*", "add").unwrap();
+    db.set_merge_operator("log:*", "append").unwrap();
+    db.set_merge_operator("tags:*", "union").unwrap();
+    db.set_merge_operator("hi:*", "max").unwrap();
+
+    db.merge("c:1", 5u32).unwrap();
+    assert_eq!(db.merge("c:1", 2u8).unwrap(), 7u32);
+    assert!(db.merge("c:1", "x").is_err());
+
+    db.merge("log:1", "a").unwrap();
+    assert_eq!(db.merge("log:1", "bc").unwrap().as_str(), "abc");
+
+    db.merge("tags:1", "rust").unwrap();
+    db.merge("tags:1", "db").unwrap();
+    db.merge("tags:1", "rust").unwrap();
+    assert_eq!(db.scard("tags:1").unwrap(), 2);
+
+    db.merge("hi:1", 3i64).unwrap();
+    assert_eq!(db.merge("hi:1", 1i64).unwrap(), 3i64);
+}
+
+#[test]
+fn registered_operator() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.register_merge_operator("concat_list", |v: Option<&BvObject>, op: &BvObject| {
+        let mut list: Vec<String> = v.map(|v| v.extract()).unwrap_or_default();
+        list.push(op.extract());
+        Ok(icbiadb::utils::serialize_object(&list))
+    });
+    db.set_merge_operator("l", "concat_list").unwrap();
+    db.merge("l", "a").unwrap();
+    db.merge("l", "b").unwrap();
+    assert_eq!(db.get_value::<Vec<String>>("l"), vec!["a", "b"]);
+
+    assert!(db.remove_merge_operator("l"));
+    assert!(db.merge("l", "c").is_err());
+}
+
+#[test]
+fn validated_against_schema() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.key_schema(KeySchema::new("log:*").max_len(3));
+    db.set_merge_operator("log:*", "append").unwrap();
+    db.merge("log:1", "abc").unwrap();
+    assert!(db.merge("log:1", "d").is_err());
+    assert_eq!(db.get_value::<String>("log:1"), "abc");
+}
+
+#[test]
+fn union_of_sets() {
+    use icbiadb::kv::merge::union;
+    use icbiadb::types::bv::BvSet;
+    use icbiadb::utils::serialize_object;
+
+    let mut operand = BvSet::new_object();
+    let mut set = BvSet::from(&mut operand).unwrap();
+    set.insert(&serialize_object("a"));
+    set.insert(&serialize_object("b"));
+
+    let merged = union(
+        Some(&union(None, &serialize_object("a")).unwrap()),
+        &operand,
+    )
+    .unwrap();
+    let members = icbiadb::types::bv::bvset::members(&merged);
+    assert_eq!(members.len(), 2);
+    assert!(!members.contains(&operand));
+}
@@ -0,0 +1,100 @@
+use icbiadb::storage::BTreeMap;
+use icbiadb::types::bv::numeric::{NumOp, Overflow};
+use icbiadb::utils::serialize_object;
+
+#[test]
+fn incr_decr() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    assert_eq!(db.decr("missing").unwrap(), -1i64);
+    assert_eq!(db.incr("n").unwrap(), 1i64);
+
+    db.set("small", 250u8).unwrap();
+    assert_eq!(db.incr_by("small", 5).unwrap().as_u8(), 255);
+    assert!(db.incr("small").is_err());
+    assert_eq!(db.get_value::<u8>("small"), 255);
+    assert_eq!(
+        db.decr_by("small", -5i64).unwrap_err(),
+        "Result overflows type \"u8\""
+    );
+    assert!(db.decr_by("small", 300u32).is_err());
+
+    db.set("s", "x").unwrap();
+    assert!(db.incr("s").is_err());
+
+    db.set_ex("t", 1, std::time::Duration::from_secs(60))
+        .unwrap();
+    db.incr("t").unwrap();
+    assert!(db.ttl("t").is_some());
+}
+
+#[test]
+fn keeps_stored_type() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set("small", 250u8).unwrap();
+    assert_eq!(
+        db.compute("small", NumOp::Add, 10, Overflow::Saturating)
+            .unwrap()
+            .type_name()
+            .as_str(),
+        "u8"
+    );
+    assert_eq!(
+        db.compute("small", NumOp::Sub, 1000, Overflow::Saturating)
+            .unwrap()
+            .as_u8(),
+        0
+    );
+    assert_eq!(db.incr_by("small", -0i8).unwrap().as_u8(), 0);
+
+    db.set("f", 1.5f32).unwrap();
+    let f = db.incr("f").unwrap();
+    assert_eq!(f.len(), 4);
+    assert_eq!(f.as_f32(), 2.5);
+    assert_eq!(db.incr_by("f", 0.5f64).unwrap().as_f64(), 3.0);
+    assert!(db
+        .compute("f", NumOp::Div, 0, Overflow::Saturating)
+        .is_err());
+}
+
+#[test]
+fn edges_of_128_bit_types() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set("big", i128::MIN).unwrap();
+    assert!(db
+        .compute("big", NumOp::Mul, -1, Overflow::Checked)
+        .is_err());
+    assert_eq!(
+        db.compute("big", NumOp::Mul, -1, Overflow::Saturating)
+            .unwrap()
+            .as_i128(),
+        i128::MAX
+    );
+
+    db.set("u", u128::MAX).unwrap();
+    assert_eq!(
+        db.compute("u", NumOp::Div, -1, Overflow::Saturating)
+            .unwrap()
+            .as_u128(),
+        0
+    );
+    assert!(db.compute("u", NumOp::Min, -5, Overflow::Checked).is_err());
+}
+
+#[test]
+fn min_max_and_mixed_operands() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    assert_eq!(
+        db.compute("m", NumOp::Max, 7u16, Overflow::Checked)
+            .unwrap(),
+        7u16
+    );
+    assert_eq!(
+        db.compute("m", NumOp::Max, 3, Overflow::Checked).unwrap(),
+        7u16
+    );
+
+    let a = serialize_object(&-7i8);
+    let b = serialize_object(&2u64);
+    assert_eq!(a.checked_div(&b).unwrap(), serialize_object(&-3i8));
+    assert_eq!(a.num_max(&b).unwrap().as_i8(), 2);
+}
@@ -0,0 +1,40 @@
+use icbiadb::storage::BTreeMap;
+
+#[test]
+fn backlog_replay() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    assert_eq!(db.publish("news.tech", "lost"), 0);
+    db.set_pubsub_backlog(2);
+    db.publish("news.tech", 1);
+    db.publish("news.art", 2);
+    db.publish("news.tech", 3);
+
+    let exact = db.subscribe("news.tech");
+    let pattern = db.psubscribe("news.*");
+    assert_eq!(
+        exact
+            .try_iter()
+            .map(|m| m.payload.extract::<i32>())
+            .collect::<Vec<_>>(),
+        vec![3]
+    );
+    assert_eq!(pattern.try_iter().count(), 2);
+}
+
+#[test]
+fn delivery_and_dropped_subscribers() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    let exact = db.subscribe("news.tech");
+    let pattern = db.psubscribe("news.*");
+    assert_eq!(db.numsub("news.tech"), 2);
+
+    let handle = std::thread::spawn(move || pattern.recv().unwrap());
+    assert_eq!(db.publish("news.art", "hello"), 1);
+    let m = handle.join().unwrap();
+    assert_eq!(m.channel, "news.art");
+    assert_eq!(m.payload.as_str(), "hello");
+
+    drop(exact);
+    assert_eq!(db.publish("news.tech", 0), 0);
+    assert_eq!(db.numsub("news.tech"), 0);
+}
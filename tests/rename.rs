@@ -0,0 +1,71 @@
+use icbiadb::kv::types::KeySchema;
+use icbiadb::storage::BTreeMap;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+struct Book {
+    title: String,
+}
+
+#[test]
+fn rename_keeps_ttl_and_indexes() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.create_field_index::<Book, _, _, _>("by_title", "book:", "title")
+        .unwrap();
+    db.create_index("by_v", "").unwrap();
+    db.set("book:1", Book { title: "a".into() }).unwrap();
+    db.set_ex("t", 5, std::time::Duration::from_secs(100))
+        .unwrap();
+
+    db.rename("t", "u").unwrap();
+    assert!(db.get("t").is_none());
+    assert!(db.ttl("u").is_some());
+    assert_eq!(db.index("by_v").unwrap().get(5).len(), 1);
+    assert!(db.rename("nope", "x").is_err());
+
+    db.rename("book:1", "book:2").unwrap();
+    assert_eq!(
+        db.index("by_title").unwrap().get("a")[0].0.as_str(),
+        "book:2"
+    );
+}
+
+#[test]
+fn rename_nx_and_copy() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set_ex("u", 5, std::time::Duration::from_secs(100))
+        .unwrap();
+    db.set("x", 1).unwrap();
+
+    assert_eq!(db.rename_nx("u", "x"), Ok(false));
+    assert_eq!(db.copy("u", "x", false), Ok(false));
+    assert_eq!(db.copy("u", "x", true), Ok(true));
+    assert_eq!(db.get_value::<i32>("x"), 5);
+    assert!(db.ttl("x").is_some());
+    assert_eq!(db.get_value::<i32>("u"), 5);
+}
+
+#[test]
+fn rename_prefix() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set("a:1", 1).unwrap();
+    db.set("a:2", 2).unwrap();
+    db.set("a:b:1", 3).unwrap();
+    assert_eq!(db.rename_prefix("a:", "a:b:"), Ok(3));
+    assert_eq!(db.keys_of_type::<i32>().len(), 3);
+    assert_eq!(db.get_value::<i32>("a:b:b:1"), 3);
+
+    // Collisions abort the whole rename
+    db.set("c:1", 1).unwrap();
+    db.set("d:1", 1).unwrap();
+    assert!(db.rename_prefix("c:", "d:").is_err());
+    assert_eq!(db.get_value::<i32>("c:1"), 1);
+}
+
+#[test]
+fn unique_schema() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.key_schema(KeySchema::new("u:*").unique());
+    db.set("u:1", "same").unwrap();
+    db.rename("u:1", "u:2").unwrap();
+    assert!(db.copy("u:2", "u:3", false).is_err());
+}
@@ -0,0 +1,51 @@
+use icbiadb::storage::BTreeMap;
+
+fn populated() -> icbiadb::KvDb<BTreeMap> {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    for i in 0..25 {
+        db.set(format!("user:{:02}", i), i).unwrap();
+        db.set(format!("post:{:02}", i), "x").unwrap();
+    }
+    db
+}
+
+#[test]
+fn pages_with_match() {
+    let db = populated();
+    let mut seen = Vec::new();
+    let mut cursor = String::new();
+    let mut pages = 0;
+    loop {
+        let (next, page) = db.scan(&cursor, 10, Some("user:*"), None);
+        seen.extend(page.iter().map(|(k, _)| k.to_string()));
+        pages += 1;
+        if next.is_empty() {
+            break;
+        }
+        cursor = next;
+    }
+
+    assert_eq!(seen.len(), 25);
+    assert_eq!(pages, 3);
+    assert!(seen.windows(2).all(|w| w[0] < w[1]));
+}
+
+#[test]
+fn type_filter_and_concurrent_changes() {
+    let mut db = populated();
+    let (cursor, page) = db.scan("", 10, None, Some("str"));
+    assert_eq!(page.len(), 10);
+    assert!(page.iter().all(|(k, _)| k.to_string().starts_with("post:")));
+
+    // Keys after the cursor are visible, keys before it are not
+    db.del("post:15");
+    db.set("post:105", "y").unwrap();
+    db.set("post:000", "z").unwrap();
+    let (next, page) = db.scan(&cursor, 100, None, Some("str"));
+    assert!(next.is_empty());
+    let keys: Vec<String> = page.iter().map(|(k, _)| k.to_string()).collect();
+    assert!(keys.contains(&"post:105".to_string()));
+    assert!(!keys.contains(&"post:15".to_string()));
+    assert!(!keys.contains(&"post:000".to_string()));
+    assert_eq!(keys.len(), 15);
+}
@@ -0,0 +1,160 @@
+use icbiadb::kv::types::KeySchema;
+use icbiadb::storage::BTreeMap;
+
+#[test]
+fn set_validation() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.key_schema(
+        KeySchema::new("article:*:url")
+            .type_name("str")
+            .max_len(10)
+            .unique()
+            .required(),
+    );
+    db.key_schema(KeySchema::new("article:[0-9]*:views").type_name("i32"));
+
+    assert!(db.set("article:0:url", "http://a").is_ok());
+    let e = db.set("article:1:url", "http://a").unwrap_err();
+    assert!(e.contains("not unique"), "{}", e);
+    // A key may keep its own value
+    assert!(db.set("article:0:url", "http://a").is_ok());
+    assert!(db
+        .set("article:1:url", "http://abcdef")
+        .unwrap_err()
+        .contains("maximum length"));
+    assert!(db
+        .set("article:1:url", 5)
+        .unwrap_err()
+        .contains("expected type"));
+    assert!(db
+        .set("article:1:url", "")
+        .unwrap_err()
+        .contains("required"));
+
+    assert!(db.set("article:1:views", 5).is_ok());
+    assert!(db.set("article:x:views", 5u8).is_ok());
+    assert!(db.set_as("article:2:views", "i32", 5).is_ok());
+    assert!(db.set_raw("article:3:views", "u8", vec![1]).is_err());
+}
+
+#[test]
+fn short_raw_strings() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.key_schema(KeySchema::new("s:*").max_len(10));
+    assert!(db.set_raw("s:1", "str", vec![1, 2, 3]).is_err());
+    assert!(db.set_raw("s:1", "list", vec![]).is_err());
+    assert!(!db.has_key("s:1"));
+    assert!(db.set_raw("s:1", "str", vec![0; 8]).is_ok());
+}
+
+#[test]
+fn collection_max_len() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.key_schema(KeySchema::new("c:*").max_len(2));
+
+    db.rpush("c:list", 1).unwrap();
+    db.lpush("c:list", 2).unwrap();
+    assert!(db.rpush("c:list", 3).is_err());
+    assert_eq!(db.llen("c:list").unwrap(), 2);
+
+    db.sadd("c:set", 1).unwrap();
+    db.sadd("c:set", 2).unwrap();
+    assert!(db.sadd("c:set", 3).is_err());
+    assert_eq!(db.scard("c:set").unwrap(), 2);
+
+    db.zadd("c:zset", 1.0, 1).unwrap();
+    db.zadd("c:zset", 1.0, 2).unwrap();
+    assert!(db.zadd("c:zset", 1.0, 3).is_err());
+    assert!(db.zincrby("c:zset", 1.0, 3).is_err());
+
+    db.hset("c:hash", "a", 1).unwrap();
+    db.hset("c:hash", "b", 1).unwrap();
+    assert!(db.hset("c:hash", "c", 1).is_err());
+    assert!(db.hincrby("c:hash", "c", 1).is_err());
+    assert_eq!(db.hlen("c:hash").unwrap(), 2);
+
+    db.sadd("other", 3).unwrap();
+    db.sadd("other", 4).unwrap();
+    assert!(db.sunionstore("c:union", &["c:set", "other"]).is_err());
+    assert!(!db.has_key("c:union"));
+}
+
+#[test]
+fn string_max_len() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.key_schema(KeySchema::new("s").max_len(5));
+    db.set("s", "abc").unwrap();
+    assert!(db.append("s", "def").is_err());
+    assert!(db.setrange("s", 4, "xy").is_err());
+    assert!(db.getset("s", "too long").is_err());
+    assert_eq!(db.get_value::<String>("s"), "abc");
+
+    db.key_schema(KeySchema::new("bits").max_len(1));
+    db.setbit("bits", 7, true).unwrap();
+    assert!(db.setbit("bits", 8, true).is_err());
+    assert!(!db.getbit("bits", 8).unwrap());
+}
+
+#[test]
+fn unique_collections() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.key_schema(KeySchema::new("tags:*").unique());
+    db.sadd("tags:1", "rust").unwrap();
+    db.sadd("tags:2", "db").unwrap();
+    assert!(db.sadd("tags:2", "rust").is_ok());
+    assert!(db.srem("tags:2", "db").is_err());
+    assert_eq!(db.scard("tags:2").unwrap(), 2);
+}
+
+#[test]
+fn type_of_stream_and_hll() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.key_schema(KeySchema::new("n:*").type_name("i32"));
+    assert!(db.xadd("n:stream", 1).is_err());
+    assert!(db.pfadd("n:hll", &[1]).is_err());
+    assert!(db.rpush("n:list", 1).is_err());
+    assert!(db.incr("n:1").is_err());
+    assert!(!db.has_key("n:stream") && !db.has_key("n:hll") && !db.has_key("n:list"));
+}
+
+#[test]
+fn schemas_are_persisted() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.key_schema(KeySchema::new("article:*:url").unique());
+    db.key_schema(KeySchema::new("article:*:views").type_name("i32"));
+    db.set("article:0:url", "http://a").unwrap();
+
+    let mut buf = std::io::Cursor::new(Vec::new());
+    db.commit_to(&mut buf).unwrap();
+    buf.set_position(0);
+    let mut db = icbiadb::kv::read_from::<_, BTreeMap>(buf).unwrap();
+    assert_eq!(db.key_schemas().len(), 2);
+    assert!(db.set("article:9:url", "http://a").is_err());
+    assert!(db.remove_key_schema("article:*:url"));
+    assert!(!db.remove_key_schema("article:*:url"));
+    assert!(db.set("article:9:url", "http://a").is_ok());
+}
+
+#[test]
+fn swap_validation() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.key_schema(KeySchema::new("u:*").unique());
+    db.set("u:1", 5u32).unwrap();
+    db.set("u:2", 6u32).unwrap();
+    assert!(db.set("u:2", 5u32).is_err());
+    assert!(db.swap("u:2", 5u32).is_err());
+    assert_eq!(db.get_value::<u32>("u:2"), 6);
+    assert_eq!(db.swap("u:2", 7u32).unwrap().as_u32(), 6);
+
+    assert!(db.swap("u:2", 7u8).is_err());
+    assert!(db.swap("missing", 1u32).is_err());
+}
+
+#[test]
+fn unique_across_types() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.key_schema(KeySchema::new("u:*").unique());
+    db.set("u:1", 1u32).unwrap();
+    assert!(db.set("u:2", 1i32).is_ok());
+    assert!(db.set("u:3", 1u32).is_err());
+}
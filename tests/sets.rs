@@ -0,0 +1,57 @@
+use icbiadb::storage::BTreeMap;
+
+#[test]
+fn add_remove_members() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    assert!(db.sadd("a", "x").unwrap());
+    assert!(!db.sadd("a", "x").unwrap());
+    db.sadd("a", 5).unwrap();
+    assert_eq!(db.scard("a").unwrap(), 2);
+    assert!(db.sismember("a", 5).unwrap());
+    assert!(!db.sismember("a", 6).unwrap());
+    assert!(!db.sismember("missing", 6).unwrap());
+
+    assert!(db.srem("a", "x").unwrap());
+    assert!(!db.srem("a", "x").unwrap());
+    assert!(db.srem("a", 5).unwrap());
+    assert!(!db.has_key("a"));
+}
+
+#[test]
+fn set_algebra() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    for m in &["x", "y", "z"] {
+        db.sadd("a", m).unwrap();
+    }
+    db.sadd("b", "y").unwrap();
+    db.sadd("b", "w").unwrap();
+
+    assert_eq!(db.sinter(&["a", "b"]).unwrap().len(), 1);
+    assert_eq!(db.sunion(&["a", "b"]).unwrap().len(), 4);
+    assert_eq!(db.sdiff(&["a", "b"]).unwrap().len(), 2);
+    assert_eq!(db.sinter(&["a", "missing"]).unwrap().len(), 0);
+
+    let mut members = db.smembers("b").unwrap();
+    members.sort_by(|a, b| a.as_slice().cmp(b.as_slice()));
+    assert!(members[0].as_str() == "w");
+}
+
+#[test]
+fn store() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.sadd("a", 1).unwrap();
+    db.sadd("b", 2).unwrap();
+    assert_eq!(db.sunionstore("c", &["a", "b"]).unwrap(), 2);
+    assert_eq!(db.scard("c").unwrap(), 2);
+    assert_eq!(db.sdiffstore("c", &["a", "b"]).unwrap(), 1);
+    assert_eq!(db.sinterstore("c", &["a", "b"]).unwrap(), 0);
+    assert!(!db.has_key("c"));
+}
+
+#[test]
+fn wrong_type() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set("s", 1).unwrap();
+    assert!(db.sadd("s", 1).is_err());
+    assert!(db.sunion(&["s"]).is_err());
+}
@@ -0,0 +1,77 @@
+use icbiadb::storage::BTreeMap;
+use icbiadb::types::BvObject;
+
+fn names(members: Vec<(BvObject, f64)>) -> Vec<String> {
+    members.iter().map(|(m, _)| m.extract()).collect()
+}
+
+fn abc() -> icbiadb::KvDb<BTreeMap> {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.zadd("z", 2.0, "b").unwrap();
+    db.zadd("z", 1.0, "a").unwrap();
+    db.zadd("z", 3.0, "c").unwrap();
+    db
+}
+
+#[test]
+fn add_score_rank() {
+    let mut db = abc();
+    assert!(!db.zadd("z", 4.0, "c").unwrap());
+    assert_eq!(db.zscore("z", "c").unwrap(), Some(4.0));
+    assert_eq!(db.zscore("z", "d").unwrap(), None);
+    assert_eq!(db.zrank("z", "b").unwrap(), Some(1));
+    assert_eq!(db.zcard("z").unwrap(), 3);
+
+    assert_eq!(db.zincrby("z", 5.0, "a").unwrap(), 6.0);
+    assert_eq!(db.zrank("z", "a").unwrap(), Some(2));
+    assert_eq!(db.zincrby("y", 5.0, "a").unwrap(), 5.0);
+    assert!(db.zrem("y", "a").unwrap());
+    assert!(!db.has_key("y"));
+}
+
+#[test]
+fn ranges() {
+    let db = abc();
+    assert_eq!(names(db.zrange("z", 0, -1).unwrap()), vec!["a", "b", "c"]);
+    assert_eq!(names(db.zrange("z", -2, -1).unwrap()), vec!["b", "c"]);
+    assert!(db.zrange("z", 5, 10).unwrap().is_empty());
+    assert_eq!(names(db.zrevrange("z", 0, 1).unwrap()), vec!["c", "b"]);
+    assert_eq!(names(db.zrevrange("z", -1, -1).unwrap()), vec!["a"]);
+    assert_eq!(names(db.zrevrange("z", 1, 10).unwrap()), vec!["b", "a"]);
+    assert_eq!(
+        names(db.zrangebyscore("z", 1.5, 3.0).unwrap()),
+        vec!["b", "c"]
+    );
+}
+
+#[test]
+fn reverse_range_out_of_bounds() {
+    let db = abc();
+    assert!(db.zrevrange("z", 5, 10).unwrap().is_empty());
+    assert!(db.zrevrange("z", 2, 1).unwrap().is_empty());
+    assert!(db.zrevrange("z", -10, -5).unwrap().is_empty());
+    assert_eq!(db.zrevrange("z", -10, 10).unwrap().len(), 3);
+    assert!(db.zrevrange("missing", 0, -1).unwrap().is_empty());
+}
+
+#[test]
+fn members_of_equal_score() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.zadd("t", 1.0, 5).unwrap();
+    db.zadd("t", 1.0, 3).unwrap();
+    let r = db.zrange("t", 0, -1).unwrap();
+    assert_eq!(r[0].0.extract::<i32>(), 3);
+
+    db.set("s", 1).unwrap();
+    assert!(db.zadd("s", 1.0, 1).is_err());
+}
+
+#[test]
+fn persisted() {
+    let db = abc();
+    let mut buf = std::io::Cursor::new(Vec::new());
+    db.commit_to(&mut buf).unwrap();
+    buf.set_position(0);
+    let db = icbiadb::kv::read_from::<_, BTreeMap>(buf).unwrap();
+    assert_eq!(db.zscore("z", "a").unwrap(), Some(1.0));
+}
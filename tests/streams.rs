@@ -0,0 +1,65 @@
+use icbiadb::storage::BTreeMap;
+use icbiadb::types::bv::StreamId;
+
+#[test]
+fn add_and_range() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    let a = db.xadd("s", "a").unwrap();
+    let b = db.xadd("s", "b").unwrap();
+    let c = db.xadd("s", 3).unwrap();
+    assert!(a < b && b < c);
+    assert_eq!(db.xlen("s").unwrap(), 3);
+
+    let r = db.xrange("s", b, StreamId::MAX).unwrap();
+    assert_eq!(r.len(), 2);
+    assert_eq!(r[0].1.extract::<String>(), "b");
+    let r = db.xrevrange("s", StreamId::MAX, StreamId::MIN).unwrap();
+    assert_eq!(r[0].0, c);
+    assert_eq!(format!("{}", a).parse::<StreamId>().unwrap(), a);
+}
+
+#[test]
+fn consumer_groups() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    let a = db.xadd("s", "a").unwrap();
+    let b = db.xadd("s", "b").unwrap();
+    let c = db.xadd("s", "c").unwrap();
+
+    db.xgroup_create("s", "g", StreamId::MIN).unwrap();
+    assert!(db.xgroup_create("s", "g", StreamId::MIN).is_err());
+    assert_eq!(db.xreadgroup("s", "g", 2).unwrap().len(), 2);
+    assert_eq!(db.xpending("s", "g").unwrap(), vec![a, b]);
+    assert_eq!(db.xack("s", "g", &[a]).unwrap(), 1);
+    assert_eq!(db.xreadgroup("s", "g", 10).unwrap()[0].0, c);
+    assert!(db.xreadgroup("s", "g", 10).unwrap().is_empty());
+
+    let mut buf = std::io::Cursor::new(Vec::new());
+    db.commit_to(&mut buf).unwrap();
+    buf.set_position(0);
+    let mut db = icbiadb::kv::read_from::<_, BTreeMap>(buf).unwrap();
+    assert_eq!(db.xpending("s", "g").unwrap(), vec![b, c]);
+    assert!(db.xgroup_destroy("s", "g").unwrap());
+    assert!(db.xreadgroup("s", "g", 1).is_err());
+}
+
+#[test]
+fn trim() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    for i in 0..3 {
+        db.xadd("s", i).unwrap();
+    }
+    let last = db.xadd("s", 3).unwrap();
+
+    assert_eq!(db.xtrim("s", 5).unwrap(), 0);
+    assert_eq!(db.xtrim("s", 1).unwrap(), 3);
+    assert_eq!(
+        db.xrange("s", StreamId::MIN, StreamId::MAX).unwrap()[0].0,
+        last
+    );
+    assert_eq!(db.xtrim("s", 0).unwrap(), 1);
+    assert_eq!(db.xlen("s").unwrap(), 0);
+
+    // IDs keep increasing after the stream has been emptied
+    assert!(db.xadd("s", "d").unwrap() > last);
+    assert_eq!(db.xlen("s").unwrap(), 1);
+}
@@ -0,0 +1,59 @@
+use icbiadb::storage::BTreeMap;
+
+#[test]
+fn append_and_setrange() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set("greeting", "Hello").unwrap();
+    assert_eq!(db.append("greeting", " World!").unwrap(), 12);
+    assert_eq!(db.get_value::<String>("greeting"), "Hello World!");
+    assert_eq!(db.setrange("greeting", 6, "Rust!!").unwrap(), 12);
+    assert_eq!(db.get("greeting").unwrap().as_str(), "Hello Rust!!");
+    assert_eq!(db.setrange("greeting", 11, "???").unwrap(), 14);
+    assert_eq!(db.get_value::<String>("greeting"), "Hello Rust!???");
+    assert_eq!(db.strlen("greeting").unwrap(), 14);
+
+    assert_eq!(db.setrange("pad", 3, "x").unwrap(), 4);
+    assert_eq!(db.get_value::<String>("pad"), "\0\0\0x");
+    assert_eq!(db.append("new", "abc").unwrap(), 3);
+}
+
+#[test]
+fn setrange_offset_overflow() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set("s", "abc").unwrap();
+    assert!(db.setrange("s", usize::MAX, "x").is_err());
+    assert!(db.setrange("missing", usize::MAX - 1, "xyz").is_err());
+    assert_eq!(db.get_value::<String>("s"), "abc");
+    assert!(!db.has_key("missing"));
+}
+
+#[test]
+fn getrange() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set("s", "Hello???").unwrap();
+    assert_eq!(db.getrange("s", 0, 4).unwrap(), "Hello");
+    assert_eq!(db.getrange("s", -3, -1).unwrap(), "???");
+    assert_eq!(db.getrange("missing", 0, 4).unwrap(), "");
+}
+
+#[test]
+fn character_boundaries() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set("u", "åäö").unwrap();
+    assert!(db.getrange("u", 0, 0).is_err());
+    assert!(db.setrange("u", 1, "a").is_err());
+    assert_eq!(db.getrange("u", 0, 1).unwrap(), "å");
+}
+
+#[test]
+fn getset_getdel() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set("u", "old").unwrap();
+    assert_eq!(db.getset("u", "new").unwrap().unwrap(), "old");
+    assert_eq!(db.getdel("u").unwrap().unwrap(), "new");
+    assert!(!db.has_key("u"));
+    assert_eq!(db.getset("none", "v").unwrap(), None);
+
+    db.set("i", 1).unwrap();
+    assert!(db.append("i", "x").is_err());
+}
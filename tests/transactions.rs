@@ -0,0 +1,100 @@
+use icbiadb::if_not_exists_create;
+use icbiadb::storage::BTreeMap;
+
+fn article(title: &str, date: &str) -> icbiadb::TableRow {
+    let mut row = icbiadb::TableRow::default();
+    row.set_col("title", title);
+    row.set_col("date", date);
+    row
+}
+
+#[test]
+fn kv_nested_transactions() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set("a", 1).unwrap();
+
+    db.begin();
+    db.set("b", 2).unwrap();
+    db.begin();
+    db.set("c", 3).unwrap();
+    db.rollback().unwrap();
+    assert!(db.get("c").is_none());
+    assert!(db.get("b").is_some());
+    db.end().unwrap();
+
+    assert!(!db.in_transaction());
+    assert_eq!(db.len(), 2);
+    assert!(db.end().is_err());
+    assert!(db.rollback().is_err());
+}
+
+#[test]
+fn kv_savepoints() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set("a", 1).unwrap();
+    db.begin();
+    db.set("b", 2).unwrap();
+    db.savepoint("sp1");
+    db.set("c", 3).unwrap();
+    db.del("a");
+
+    db.rollback_to("sp1").unwrap();
+    assert!(db.get("c").is_none());
+    assert!(db.get("a").is_some());
+
+    // The savepoint is kept after rolling back to it
+    db.set("d", 4).unwrap();
+    db.rollback_to("sp1").unwrap();
+    assert!(db.get("d").is_none());
+
+    db.set("d", 4).unwrap();
+    db.release("sp1").unwrap();
+    assert!(db.rollback_to("sp1").is_err());
+    db.end().unwrap();
+    assert_eq!(db.len(), 3);
+}
+
+#[test]
+fn kv_savepoint_below_transaction() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.begin();
+    db.savepoint("sp1");
+    db.set("a", 1).unwrap();
+    db.begin();
+    db.set("b", 2).unwrap();
+
+    // The inner transaction has to be closed before sp1 can be released
+    assert!(db.release("sp1").is_err());
+    assert!(db.rollback_to("sp1").is_err());
+    db.rollback().unwrap();
+    assert!(db.get("b").is_none());
+
+    db.release("sp1").unwrap();
+    db.end().unwrap();
+    assert!(!db.in_transaction());
+    assert_eq!(db.len(), 1);
+}
+
+#[test]
+fn table_savepoints() {
+    let mut db = icbiadb::table::mem();
+    if_not_exists_create! {db, "articles", (title: String, date: String[unique])};
+
+    db.begin();
+    db.insert_row("articles", article("a", "today")).unwrap();
+    db.savepoint("sub");
+    db.insert_row("articles", article("b", "tomorrow")).unwrap();
+    db.rollback_to("sub").unwrap();
+    assert_eq!(db.rows("articles").len(), 1);
+
+    // Unique values of rolled back rows are free again
+    db.insert_row("articles", article("b", "tomorrow")).unwrap();
+    assert!(db.insert_row("articles", article("c", "today")).is_err());
+    db.end().unwrap();
+
+    db.begin();
+    db.insert_row("articles", article("d", "yesterday"))
+        .unwrap();
+    db.rollback().unwrap();
+    assert_eq!(db.rows("articles").len(), 2);
+}
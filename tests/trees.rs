@@ -0,0 +1,90 @@
+use icbiadb::storage::BTreeMap;
+
+fn open(path: &std::path::Path) -> icbiadb::KvDb<BTreeMap> {
+    icbiadb::kv::create::<BTreeMap>(path.to_str().unwrap()).unwrap()
+}
+
+#[test]
+fn commit_and_commit_tree() {
+    let path = std::env::temp_dir().join("icbiadb_test_trees.idb");
+    let _ = std::fs::remove_file(&path);
+
+    let mut db = open(&path);
+    db.set("main", 1).unwrap();
+    db.open_tree("tenant_a").set("k", "a").unwrap();
+    db.open_tree("tenant_a")
+        .set_ex("t", 1, std::time::Duration::from_secs(60))
+        .unwrap();
+    db.open_tree("tenant_b").set("k", "b").unwrap();
+    db.open_tree("tenant_b")
+        .open_tree("nested")
+        .set("n", 5)
+        .unwrap();
+    assert_eq!(db.list_trees(), vec!["tenant_a", "tenant_b"]);
+    assert_eq!(db.len(), 1);
+    db.commit().unwrap();
+
+    let mut db = open(&path);
+    assert_eq!(db.tree("tenant_a").unwrap().len(), 2);
+    assert!(db.tree("tenant_a").unwrap().ttl("t").is_some());
+    assert_eq!(
+        db.tree("tenant_b")
+            .unwrap()
+            .tree("nested")
+            .unwrap()
+            .get_value::<i32>("n"),
+        5
+    );
+
+    // Only the named tree is written
+    db.set("main", 2).unwrap();
+    db.open_tree("tenant_a").set("k2", "x").unwrap();
+    db.drop_tree("tenant_b");
+    db.commit_tree("tenant_a").unwrap();
+
+    let mut db = open(&path);
+    assert_eq!(db.get_value::<i32>("main"), 1);
+    assert_eq!(db.tree("tenant_a").unwrap().len(), 3);
+    assert!(db.tree("tenant_b").is_some());
+    db.drop_tree("tenant_b");
+    db.commit_tree("tenant_b").unwrap();
+
+    let db = open(&path);
+    assert_eq!(db.list_trees(), vec!["tenant_a"]);
+    assert_eq!(db.get_value::<i32>("main"), 1);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn commit_without_file() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    assert_eq!(
+        db.open_tree("t").commit().unwrap_err().kind(),
+        std::io::ErrorKind::InvalidInput
+    );
+    assert!(db.commit_tree("t").is_err());
+}
+
+#[test]
+fn rollback_covers_trees() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.open_tree("a").set("k", 1).unwrap();
+    db.open_tree("dropped").set("k", 1).unwrap();
+
+    db.begin();
+    db.open_tree("a").set("k", 2).unwrap();
+    db.open_tree("a").set("new", 2).unwrap();
+    db.open_tree("created").set("k", 1).unwrap();
+    db.drop_tree("dropped");
+    db.rollback().unwrap();
+
+    assert_eq!(db.list_trees(), vec!["a", "dropped"]);
+    assert_eq!(db.tree("a").unwrap().get_value::<i32>("k"), 1);
+    assert!(db.tree("a").unwrap().get("new").is_none());
+    assert_eq!(db.tree("dropped").unwrap().get_value::<i32>("k"), 1);
+
+    db.savepoint("sp");
+    db.open_tree("a").set("k", 3).unwrap();
+    db.rollback_to("sp").unwrap();
+    assert_eq!(db.tree("a").unwrap().get_value::<i32>("k"), 1);
+}
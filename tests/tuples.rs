@@ -0,0 +1,184 @@
+use icbiadb::kv::types::{CacheConfig, EvictionPolicy, KeySchema};
+use icbiadb::storage::BTreeMap;
+
+#[test]
+fn update_elements() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set("t", (100, 100u8, "hello", 7i64)).unwrap();
+    db.update_tuple("t", |t| {
+        assert_eq!(t.len(), 4);
+        t.set(1, 5u8)?;
+        assert!(t.set(1, 5i32).is_err());
+        t.set(2, "hello world")?;
+        assert_eq!(t.value::<String>(2)?, "hello world");
+        assert_eq!(t.value::<i64>(3)?, 7);
+        t.set(2, "hi")?;
+        assert_eq!(t.incr_elem(3, 3)?, 10i64);
+        assert_eq!(t.decr_elem(0, 1)?, 99);
+        assert!(t.incr_elem(1, 300).is_err());
+        assert!(t.incr_elem(2, 1).is_err());
+        assert!(t.value::<i32>(9).is_err());
+        assert!(t.set(9, 1).is_err());
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(
+        db.get_value::<(i32, u8, String, i64)>("t"),
+        (99, 5, "hi".to_string(), 10)
+    );
+
+    assert!(db.update_tuple("missing", |_| Ok(())).is_err());
+    db.set("n", 1).unwrap();
+    assert!(db.update_tuple("n", |_| Ok(())).is_err());
+}
+
+#[test]
+fn get_tuple_is_read_only() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set("t", (1, "a")).unwrap();
+    let mut t = db.get_tuple("t").unwrap();
+    assert_eq!(t.value::<String>(1).unwrap(), "a");
+    assert!(t.set(1, "b").is_err());
+    assert!(t.incr_elem(0, 1).is_err());
+    assert_eq!(db.get_value::<(i32, String)>("t"), (1, "a".to_string()));
+
+    db.set("n", 1).unwrap();
+    assert!(db.get_tuple("n").is_err());
+    assert!(db.get_tuple("missing").is_err());
+    db.set("v", (1, vec![1])).unwrap();
+    assert_eq!(
+        db.get_tuple("v").unwrap().value::<Vec<i32>>(1).unwrap(),
+        vec![1]
+    );
+
+    #[derive(serde::Serialize)]
+    struct S(u8);
+    db.set("s", (1, S(1))).unwrap();
+    assert!(db.get_tuple("s").is_err());
+}
+
+#[test]
+fn failed_update_keeps_record() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set("t", (1, "a")).unwrap();
+    let r = db.update_tuple("t", |t| {
+        t.set(1, "changed")?;
+        t.set(0, "wrong type")
+    });
+    assert!(r.is_err());
+    assert_eq!(db.get_value::<(i32, String)>("t"), (1, "a".to_string()));
+
+    db.key_schema(KeySchema::new("t").max_len(20));
+    assert!(db.update_tuple("t", |t| t.set(1, "x".repeat(100))).is_err());
+    assert_eq!(db.get_value::<(i32, String)>("t"), (1, "a".to_string()));
+}
+
+#[test]
+fn updates_are_tracked() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.enable_journal(10);
+    db.set_cache(CacheConfig::new(EvictionPolicy::Lru).max_bytes(1000));
+    db.create_index("by_t", "").unwrap();
+    db.set("t", (1, "a")).unwrap();
+    let events = db.watch("t");
+    let bytes = db.cache_stats().unwrap().bytes;
+
+    db.update_tuple("t", |t| t.set(1, "abc")).unwrap();
+    assert_eq!(db.cache_stats().unwrap().bytes, bytes + 2);
+    assert_eq!(events.try_iter().count(), 1);
+    assert_eq!(db.index("by_t").unwrap().get((1, "abc")).len(), 1);
+
+    db.undo().unwrap();
+    assert_eq!(db.get_value::<(i32, String)>("t"), (1, "a".to_string()));
+    assert_eq!(db.index("by_t").unwrap().get((1, "a")).len(), 1);
+}
+
+#[test]
+fn element_types() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set(
+        "all",
+        (
+            true,
+            'é',
+            Some(5i32),
+            String::from("abc"),
+            (2u8, "x"),
+            [1u8, 2, 3],
+            vec![4i16, 5],
+            1.5f64,
+            2.5f32,
+            None::<u8>,
+            (7i8,),
+        ),
+    )
+    .unwrap();
+    {
+        let t = db.get_tuple("all").unwrap();
+        assert_eq!(t.len(), 11);
+        assert!(t.value::<bool>(0).unwrap());
+        assert_eq!(t.value::<char>(1).unwrap(), 'é');
+        assert_eq!(t.value::<Option<i32>>(2).unwrap(), Some(5));
+        assert_eq!(t.value::<String>(3).unwrap(), "abc");
+        assert_eq!(t.value::<(u8, String)>(4).unwrap(), (2, "x".to_string()));
+        assert_eq!(t.value::<[u8; 3]>(5).unwrap(), [1, 2, 3]);
+        assert_eq!(t.value::<Vec<i16>>(6).unwrap(), vec![4, 5]);
+        assert_eq!(t.value::<f64>(7).unwrap(), 1.5);
+        assert_eq!(t.value::<f32>(8).unwrap(), 2.5);
+        assert_eq!(t.value::<Option<u8>>(9).unwrap(), None);
+        assert_eq!(t.value::<(i8,)>(10).unwrap(), (7,));
+        assert!(&t.get(7).unwrap() == 1.5f64);
+    }
+
+    db.update_tuple("all", |t| {
+        t.set(2, None::<i32>)?;
+        t.set(6, vec![1i16, 2, 3])?;
+        t.set(9, Some(3u8))?;
+        t.set(1, 'a')?;
+        assert!(t.set(5, [1u8, 2]).is_err());
+        Ok(())
+    })
+    .unwrap();
+
+    let all = db.get_value::<(
+        bool,
+        char,
+        Option<i32>,
+        String,
+        (u8, String),
+        [u8; 3],
+        Vec<i16>,
+        f64,
+        f32,
+        Option<u8>,
+        (i8,),
+    )>("all");
+    assert_eq!(all.1, 'a');
+    assert_eq!(all.2, None);
+    assert_eq!(all.6, vec![1, 2, 3]);
+    assert_eq!(all.9, Some(3));
+    assert_eq!(all.10, (7,));
+}
+
+#[test]
+fn f32_elements() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set("t", (1.5f32, 2u8)).unwrap();
+    let t = db.get_tuple("t").unwrap();
+    let elem = t.get(0).unwrap();
+    assert_eq!(elem.type_name().as_str(), "f64");
+    assert_eq!(elem.as_f32(), 1.5);
+    assert_eq!(elem.as_f64(), 1.5);
+    assert!(&elem == 1.5f64);
+    assert!(&elem < 2.0f64);
+}
+
+#[test]
+fn oversized_string_length() {
+    use icbiadb::types::bv::layout::Layout;
+
+    let layout = Layout::parse("(u8, String)").unwrap();
+    let mut bytes = vec![1];
+    bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+    assert!(layout.encoded_len(&bytes).is_err());
+}
@@ -0,0 +1,59 @@
+use icbiadb::storage::BTreeMap;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+struct Article {
+    title: String,
+}
+
+fn populated() -> icbiadb::KvDb<BTreeMap> {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    db.set("a:1", Article { title: "x".into() }).unwrap();
+    db.set("a:2", &Article { title: "y".into() }).unwrap();
+    db.set("s", "str").unwrap();
+    db.set("n", 1i32).unwrap();
+    db.lpush("l", 1).unwrap();
+    db
+}
+
+#[test]
+fn keys_and_values_of_type() {
+    let db = populated();
+    assert_eq!(db.keys_of_type::<Article>().len(), 2);
+    assert_eq!(
+        db.values_of_type::<Article>()[1].1,
+        Article { title: "y".into() }
+    );
+    assert_eq!(db.keys_of_type::<String>().len(), 1);
+    assert_eq!(db.keys_of_type::<&str>().len(), 1);
+    assert_eq!(db.keys_of_type_name("list").len(), 1);
+}
+
+#[test]
+fn follows_overwrites_and_deletes() {
+    let mut db = populated();
+    db.set("s", 5i32).unwrap();
+    assert_eq!(db.keys_of_type::<String>().len(), 0);
+    assert_eq!(db.keys_of_type::<i32>().len(), 2);
+
+    db.del("n");
+    let counts = db.count_by_type();
+    assert_eq!(counts.get("i32"), Some(&1));
+    assert_eq!(counts.get("str"), None);
+
+    db.begin();
+    db.set("x", "y").unwrap();
+    db.rollback().unwrap();
+    assert_eq!(db.keys_of_type::<String>().len(), 0);
+}
+
+#[test]
+fn rebuilt_on_load() {
+    let mut db = populated();
+    let mut buf = std::io::Cursor::new(Vec::new());
+    db.commit_to(&mut buf).unwrap();
+    buf.set_position(0);
+
+    let db = icbiadb::kv::read_from::<_, BTreeMap>(buf).unwrap();
+    assert_eq!(db.keys_of_type::<Article>().len(), 2);
+    assert_eq!(db.keys_of_type_name("list").len(), 1);
+}
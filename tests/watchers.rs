@@ -0,0 +1,48 @@
+use icbiadb::if_not_exists_create;
+use icbiadb::kv::types::EventKind;
+use icbiadb::storage::BTreeMap;
+
+#[test]
+fn kv_watch_prefix() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    let events = db.watch_prefix("session:");
+    db.set("session:1", 1).unwrap();
+    db.set("session:1", 2).unwrap();
+    db.set("other", 2).unwrap();
+    db.del("session:1");
+
+    let events: Vec<_> = events.try_iter().collect();
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[0].kind, EventKind::Insert);
+    assert!(events[0].old.is_none());
+    assert_eq!(events[1].kind, EventKind::Update);
+    assert!(events[1].old.as_ref().unwrap() == &1);
+    assert!(events[1].new.as_ref().unwrap() == &2);
+    assert_eq!(events[2].kind, EventKind::Delete);
+    assert_eq!(events[2].key.as_str(), "session:1");
+}
+
+#[test]
+fn kv_watch_key() {
+    let mut db = icbiadb::kv::mem::<BTreeMap>();
+    let events = db.watch("x");
+    db.set("x", 1).unwrap();
+    db.set("xy", 1).unwrap();
+    assert_eq!(events.try_iter().count(), 1);
+
+    // Dropped receivers are removed on the next event
+    drop(events);
+    db.set("x", 2).unwrap();
+}
+
+#[test]
+fn table_watch() {
+    let mut db = icbiadb::table::mem();
+    if_not_exists_create! {db, "articles", (title: String)};
+    let events = db.watch("articles");
+
+    let mut row = icbiadb::TableRow::default();
+    row.set_col("title", "a");
+    db.insert_row("articles", row).unwrap();
+    assert_eq!(events.try_iter().count(), 1);
+}